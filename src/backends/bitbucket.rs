@@ -0,0 +1,510 @@
+//! A module to post feedback via Bitbucket Cloud's REST API.
+//!
+//! Bitbucket does not render HTML in comments, so the comment is converted
+//! with [`to_bitbucket_markdown()`] before it is posted.
+use super::{
+    CommentPage, REPORT_FILE, api_base_url, append_report_file, log_output_variables,
+    remove_bot_comments,
+};
+use async_trait::async_trait;
+use git_bot_feedback::{
+    CommentPolicy, OutputVariable, RestApiClient, RestApiRateLimitHeaders, RestClientError,
+    ReviewOptions, ThreadCommentOptions, client::USER_AGENT,
+};
+use reqwest::{
+    Client, Method, Request, Url,
+    header::{HeaderMap, HeaderValue},
+};
+use serde::Deserialize;
+use std::{env, path::PathBuf};
+
+/// The base URL of Bitbucket Cloud's REST API.
+pub const BITBUCKET_API_URL: &str = "https://api.bitbucket.org/2.0";
+
+/// A structure for deserializing a page of Pull Request comments from a response's JSON.
+#[derive(Debug, Deserialize)]
+struct BitbucketCommentPage {
+    values: Vec<Comment>,
+
+    /// The URL of the next page (if any).
+    next: Option<String>,
+}
+
+/// A structure for deserializing a Pull Request comment from a response's JSON.
+#[derive(Debug, Deserialize)]
+struct Comment {
+    id: u64,
+    content: CommentContent,
+    #[serde(default)]
+    deleted: bool,
+}
+
+/// A structure for deserializing the content of a Pull Request comment.
+#[derive(Debug, Deserialize)]
+struct CommentContent {
+    raw: String,
+}
+
+/// The credentials used to authenticate REST API requests.
+#[derive(Debug, Clone)]
+pub enum Credentials {
+    /// A username and app password (used with HTTP basic authentication).
+    AppPassword { username: String, password: String },
+
+    /// A repository, project or workspace access token (used as a bearer token).
+    AccessToken(String),
+}
+
+/// Convert the given markdown `text` into markdown that Bitbucket can render.
+///
+/// Bitbucket does not support HTML in comments, so
+///
+/// - HTML comments (like the comment's marker) are converted into hidden link references.
+/// - `<details>` blocks are expanded; the `<summary>` is shown in bold.
+pub fn to_bitbucket_markdown(text: &str) -> String {
+    text.split('\n')
+        .filter(|line| line.trim() != "</details>")
+        .map(|line| {
+            let trimmed = line.trim();
+            if let Some(hidden) = trimmed
+                .strip_prefix("<!--")
+                .and_then(|l| l.strip_suffix("-->"))
+            {
+                format!("[//]: # ({})", hidden.trim())
+            } else if let Some(summary) = trimmed
+                .strip_prefix("<details><summary>")
+                .and_then(|l| l.strip_suffix("</summary>"))
+            {
+                format!("**{summary}**")
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A structure to work with Bitbucket Cloud REST API.
+pub struct BitbucketApiClient {
+    /// The HTTP request client to be used for all REST API calls.
+    client: Client,
+
+    /// The base URL of Bitbucket's REST API.
+    api_url: Url,
+
+    /// The value of the `BITBUCKET_REPO_FULL_NAME` environment variable.
+    repo: String,
+
+    /// The value of the `BITBUCKET_PR_ID` environment variable.
+    ///
+    /// This is [`None`] for pipelines that do not run for a Pull Request.
+    pr_id: Option<u64>,
+
+    /// The credentials used to authenticate REST API requests.
+    credentials: Option<Credentials>,
+
+    /// The file that the report is written to when not running for a Pull Request.
+    pub report_file: PathBuf,
+
+    /// The response header names that describe the rate limit status.
+    rate_limit_headers: RestApiRateLimitHeaders,
+}
+
+impl BitbucketApiClient {
+    /// Instantiate a [`BitbucketApiClient`] object.
+    ///
+    /// The given `api_url` should be the base URL of Bitbucket's REST API (see [`BITBUCKET_API_URL`]).
+    pub fn new(
+        api_url: &str,
+        repo: String,
+        pr_id: Option<u64>,
+        credentials: Option<Credentials>,
+    ) -> Result<Self, RestClientError> {
+        let api_url = api_base_url(api_url)?;
+        if credentials.is_none() {
+            log::warn!(
+                "No BITBUCKET_USERNAME and BITBUCKET_APP_PASSWORD environment variables found! Permission to post comments may be unsatisfied."
+            );
+        }
+        Ok(Self {
+            client: Self::make_client(USER_AGENT)?,
+            api_url,
+            repo,
+            pr_id,
+            credentials,
+            report_file: PathBuf::from(REPORT_FILE),
+            rate_limit_headers: RestApiRateLimitHeaders {
+                reset: "x-ratelimit-reset".to_string(),
+                remaining: "x-ratelimit-remaining".to_string(),
+                retry: "retry-after".to_string(),
+            },
+        })
+    }
+
+    /// Instantiate a [`BitbucketApiClient`] object from Bitbucket Pipelines' default environment variables.
+    ///
+    /// The app password is read from the `BITBUCKET_USERNAME` and `BITBUCKET_APP_PASSWORD`
+    /// (user-defined) environment variables.
+    /// If those are not set, then the given `token` is used as an access token.
+    pub fn from_env(token: Option<String>) -> Result<Self, RestClientError> {
+        let repo = env::var("BITBUCKET_REPO_FULL_NAME")
+            .map_err(|e| RestClientError::env_var("BITBUCKET_REPO_FULL_NAME", e))?;
+        let pr_id = match env::var("BITBUCKET_PR_ID") {
+            Ok(id) => Some(id.parse::<u64>().map_err(|_| {
+                RestClientError::MalformedEventInfo(format!("BITBUCKET_PR_ID={id}"))
+            })?),
+            Err(_) => None,
+        };
+        let credentials = match (
+            env::var("BITBUCKET_USERNAME"),
+            env::var("BITBUCKET_APP_PASSWORD"),
+        ) {
+            (Ok(username), Ok(password)) => Some(Credentials::AppPassword { username, password }),
+            _ => token.map(Credentials::AccessToken),
+        };
+        Self::new(BITBUCKET_API_URL, repo, pr_id, credentials)
+    }
+
+    fn make_client(user_agent: &str) -> Result<Client, RestClientError> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", HeaderValue::from_static("application/json"));
+        Ok(Client::builder()
+            .default_headers(headers)
+            .user_agent(user_agent)
+            .build()?)
+    }
+
+    /// Build an authenticated request.
+    ///
+    /// This is used instead of [`RestApiClient::make_api_request()`] because
+    /// app passwords require HTTP basic authentication.
+    fn make_request(
+        &self,
+        url: Url,
+        method: Method,
+        data: Option<String>,
+    ) -> Result<Request, RestClientError> {
+        let mut req = self.client.request(method, url);
+        req = match &self.credentials {
+            Some(Credentials::AppPassword { username, password }) => {
+                req.basic_auth(username, Some(password))
+            }
+            Some(Credentials::AccessToken(token)) => req.bearer_auth(token),
+            None => req,
+        };
+        if let Some(data) = data {
+            req = req.body(data);
+        }
+        Ok(req.build()?)
+    }
+
+    /// Get the URL of the Pull Request's comments.
+    fn comments_url(&self, pr_id: u64) -> Result<Url, RestClientError> {
+        Ok(self
+            .api_url
+            .join(format!("repositories/{}/pullrequests/{pr_id}/comments", self.repo).as_str())?)
+    }
+}
+
+#[async_trait]
+impl RestApiClient for BitbucketApiClient {
+    fn client_kind(&self) -> String {
+        "bitbucket".to_string()
+    }
+
+    /// Bitbucket Pipelines has no syntax to group log output.
+    fn start_log_group(&self, name: &str) {
+        log::info!(target: "CI_LOG_GROUPING", "{name}");
+    }
+
+    fn end_log_group(&self, _name: &str) {}
+
+    fn is_pr_event(&self) -> bool {
+        self.pr_id.is_some()
+    }
+
+    /// Bitbucket Pipelines has no debug mode.
+    fn is_debug_enabled(&self) -> bool {
+        false
+    }
+
+    /// Bitbucket Pipelines does not expose the event that triggered the pipeline.
+    fn event_name(&self) -> Option<String> {
+        None
+    }
+
+    fn set_user_agent(&mut self, user_agent: &str) -> Result<(), RestClientError> {
+        self.client = Self::make_client(user_agent)?;
+        Ok(())
+    }
+
+    /// Does not support pipelines that are not for a Pull Request.
+    ///
+    /// The comment (and its marker) is converted with [`to_bitbucket_markdown()`].
+    async fn post_thread_comment(
+        &self,
+        options: ThreadCommentOptions,
+    ) -> Result<(), RestClientError> {
+        let Some(pr_id) = self.pr_id else {
+            return Ok(());
+        };
+        let url = self.comments_url(pr_id)?;
+        let marker = to_bitbucket_markdown(&options.marker);
+        let comment_url = remove_bot_comments(
+            self,
+            Url::parse_with_params(url.as_str(), &[("pagelen", "100"), ("page", "1")])?,
+            &marker,
+            options.policy == CommentPolicy::Anew,
+            |url, method| async move {
+                let request = self.make_request(url, method, None)?;
+                self.send_api_request(&self.client, request, &self.rate_limit_headers)
+                    .await
+            },
+            |_, body| {
+                // Bitbucket paginates via the response's payload (not its headers)
+                let page = serde_json::from_str::<BitbucketCommentPage>(body).map_err(|e| {
+                    RestClientError::json("deserialize list of existing comments", e)
+                })?;
+                Ok(CommentPage {
+                    comments: page
+                        .values
+                        .into_iter()
+                        .filter(|c| !c.deleted)
+                        .map(|c| (c.id, c.content.raw))
+                        .collect(),
+                    next: page.next.map(|next| Url::parse(&next)).transpose()?,
+                })
+            },
+            |id| Ok(Url::parse(format!("{url}/{id}").as_str())?),
+        )
+        .await?;
+        let payload = serde_json::json!({
+            "content": { "raw": to_bitbucket_markdown(&options.mark_comment()) }
+        })
+        .to_string();
+        let method = if comment_url.is_some() {
+            Method::PUT
+        } else {
+            Method::POST
+        };
+        let request = self.make_request(comment_url.unwrap_or(url), method, Some(payload))?;
+        let response = self
+            .send_api_request(&self.client, request, &self.rate_limit_headers)
+            .await
+            .map_err(|e| e.add_request_context("post comment"))?;
+        self.log_response(response, "Failed to post comment").await;
+        Ok(())
+    }
+
+    /// Bitbucket has no step summary, so the `comment` is appended to [`Self::report_file`] instead.
+    fn append_step_summary(&self, comment: &str) -> Result<(), RestClientError> {
+        append_report_file(&self.report_file, comment)
+    }
+
+    async fn cull_pr_reviews(
+        &mut self,
+        _options: &mut ReviewOptions,
+    ) -> Result<(), RestClientError> {
+        Ok(())
+    }
+
+    async fn post_pr_review(&mut self, _options: &ReviewOptions) -> Result<(), RestClientError> {
+        Ok(())
+    }
+
+    fn write_output_variables(&self, vars: &[OutputVariable]) -> Result<(), RestClientError> {
+        log_output_variables(vars)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BitbucketApiClient, Credentials, to_bitbucket_markdown};
+    use git_bot_feedback::{CommentPolicy, RestApiClient, ThreadCommentOptions};
+    use mockito::{Matcher, Server};
+    use std::fs;
+    use tempfile::NamedTempFile;
+
+    const REPO: &str = "2bndy5/legacy-firmware";
+    const PR: u64 = 5;
+    const MARKER: &str = "<!-- marker -->\n";
+    /// The base64 encoding of `bot:secret`.
+    const BASIC_AUTH: &str = "Basic Ym90OnNlY3JldA==";
+
+    fn options(policy: CommentPolicy) -> ThreadCommentOptions {
+        ThreadCommentOptions {
+            comment: "report".to_string(),
+            marker: MARKER.to_string(),
+            policy,
+            ..Default::default()
+        }
+    }
+
+    fn credentials() -> Option<Credentials> {
+        Some(Credentials::AppPassword {
+            username: "bot".to_string(),
+            password: "secret".to_string(),
+        })
+    }
+
+    #[test]
+    fn convert_markdown() {
+        let text = "<!-- marker -->\n### Title\n\n<details><summary>Click me</summary>\n\n| a |\n\n</details>\n";
+        assert_eq!(
+            to_bitbucket_markdown(text),
+            "[//]: # (marker)\n### Title\n\n**Click me**\n\n| a |\n\n"
+        );
+    }
+
+    async fn post_comment(existing_comments: &str, policy: CommentPolicy) {
+        let mut server = Server::new_async().await;
+        let comments = format!("/2.0/repositories/{REPO}/pullrequests/{PR}/comments");
+        let mut mocks = vec![
+            server
+                .mock("GET", comments.as_str())
+                .match_query(Matcher::Any)
+                .match_header("Authorization", BASIC_AUTH)
+                .with_body(existing_comments)
+                .create(),
+        ];
+        let payload = serde_json::json!({ "content": { "raw": "[//]: # (marker)\nreport" } });
+        let has_comments = existing_comments.contains("[//]: # (marker)");
+        if has_comments {
+            // the outdated comment is always deleted
+            mocks.push(
+                server
+                    .mock("DELETE", format!("{comments}/1").as_str())
+                    .match_header("Authorization", BASIC_AUTH)
+                    .with_status(204)
+                    .create(),
+            );
+        }
+        if has_comments && policy == CommentPolicy::Update {
+            mocks.push(
+                server
+                    .mock("PUT", format!("{comments}/2").as_str())
+                    .match_body(Matcher::Json(payload))
+                    .create(),
+            );
+        } else {
+            if has_comments {
+                mocks.push(
+                    server
+                        .mock("DELETE", format!("{comments}/2").as_str())
+                        .with_status(204)
+                        .create(),
+                );
+            }
+            mocks.push(
+                server
+                    .mock("POST", comments.as_str())
+                    .match_header("Authorization", BASIC_AUTH)
+                    .match_body(Matcher::Json(payload))
+                    .with_status(201)
+                    .create(),
+            );
+        }
+
+        let client = BitbucketApiClient::new(
+            format!("{}/2.0", server.url()).as_str(),
+            REPO.to_string(),
+            Some(PR),
+            credentials(),
+        )
+        .unwrap();
+        assert!(client.is_pr_event());
+        client.post_thread_comment(options(policy)).await.unwrap();
+        for mock in mocks {
+            mock.assert();
+        }
+    }
+
+    const EXISTING_COMMENTS: &str = r#"{"values": [
+        {"id": 1, "content": {"raw": "[//]: # (marker)\nold report"}},
+        {"id": 3, "content": {"raw": "a user's comment"}},
+        {"id": 4, "content": {"raw": "[//]: # (marker)\ndeleted report"}, "deleted": true},
+        {"id": 2, "content": {"raw": "[//]: # (marker)\nlatest report"}}
+    ]}"#;
+
+    #[tokio::test]
+    async fn create_comment() {
+        post_comment(r#"{"values": []}"#, CommentPolicy::Update).await;
+    }
+
+    #[tokio::test]
+    async fn update_comment() {
+        post_comment(EXISTING_COMMENTS, CommentPolicy::Update).await;
+    }
+
+    #[tokio::test]
+    async fn replace_comments() {
+        post_comment(EXISTING_COMMENTS, CommentPolicy::Anew).await;
+    }
+
+    #[tokio::test]
+    async fn paginated_comments() {
+        let mut server = Server::new_async().await;
+        let comments = format!("/2.0/repositories/{REPO}/pullrequests/{PR}/comments");
+        let first_page = server
+            .mock("GET", comments.as_str())
+            .match_query(Matcher::UrlEncoded("page".to_string(), "1".to_string()))
+            .with_body(
+                serde_json::json!({
+                    "values": [{"id": 1, "content": {"raw": "[//]: # (marker)\nold report"}}],
+                    "next": format!("{}{comments}?page=2", server.url()),
+                })
+                .to_string(),
+            )
+            .create();
+        let second_page = server
+            .mock("GET", comments.as_str())
+            .match_query(Matcher::UrlEncoded("page".to_string(), "2".to_string()))
+            .with_body(r#"{"values": [{"id": 2, "content": {"raw": "[//]: # (marker)\nlatest"}}]}"#)
+            .create();
+        let delete = server
+            .mock("DELETE", format!("{comments}/1").as_str())
+            .with_status(204)
+            .create();
+        let update = server
+            .mock("PUT", format!("{comments}/2").as_str())
+            .match_header("Authorization", "Bearer token")
+            .create();
+
+        let client = BitbucketApiClient::new(
+            format!("{}/2.0", server.url()).as_str(),
+            REPO.to_string(),
+            Some(PR),
+            Some(Credentials::AccessToken("token".to_string())),
+        )
+        .unwrap();
+        client
+            .post_thread_comment(options(CommentPolicy::Update))
+            .await
+            .unwrap();
+        first_page.assert();
+        second_page.assert();
+        delete.assert();
+        update.assert();
+    }
+
+    #[tokio::test]
+    async fn not_a_pull_request() {
+        let report_file = NamedTempFile::new().unwrap();
+        let mut client = BitbucketApiClient::new(
+            "http://localhost/2.0",
+            REPO.to_string(),
+            None,
+            credentials(),
+        )
+        .unwrap();
+        client.report_file = report_file.path().to_path_buf();
+        assert!(!client.is_pr_event());
+        // no HTTP requests are made here
+        client
+            .post_thread_comment(options(CommentPolicy::Update))
+            .await
+            .unwrap();
+        client.append_step_summary("report").unwrap();
+        assert_eq!(fs::read_to_string(report_file).unwrap(), "\nreport\n\n");
+    }
+}
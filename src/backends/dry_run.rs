@@ -0,0 +1,120 @@
+//! A module to preview feedback locally without any CI context.
+//!
+//! Instead of making REST API calls, the [`DryRunClient`] logs the calls that would have been made
+//! and writes the comment to stdout (or a file).
+use super::log_output_variables;
+use async_trait::async_trait;
+use git_bot_feedback::{
+    CommentPolicy, FileAnnotation, OutputVariable, RestApiClient, RestClientError, ReviewOptions,
+    ThreadCommentOptions,
+};
+use std::{
+    fs,
+    io::{Write, stdout},
+    path::PathBuf,
+};
+
+/// A [`RestApiClient`] implementation that makes no REST API calls.
+#[derive(Debug, Default)]
+pub struct DryRunClient {
+    /// The file that the comment is written to.
+    ///
+    /// If [`None`], then the comment is written to stdout.
+    pub output: Option<PathBuf>,
+}
+
+impl DryRunClient {
+    /// Write the given `comment` to [`Self::output`] (or stdout).
+    fn write_comment(&self, comment: &str) -> Result<(), RestClientError> {
+        match &self.output {
+            Some(path) => {
+                fs::write(path, comment)
+                    .map_err(|e| RestClientError::io("write comment to output file", e))?;
+                log::info!("Wrote comment to {}", path.to_string_lossy());
+            }
+            None => {
+                let mut stdout = stdout().lock();
+                writeln!(stdout, "{comment}")
+                    .map_err(|e| RestClientError::io("write comment to stdout", e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RestApiClient for DryRunClient {
+    fn client_kind(&self) -> String {
+        "dry-run".to_string()
+    }
+
+    fn start_log_group(&self, _name: &str) {}
+
+    fn end_log_group(&self, _name: &str) {}
+
+    /// Always `true`, so the comment is previewed as it would be posted on a Pull Request.
+    fn is_pr_event(&self) -> bool {
+        true
+    }
+
+    fn set_user_agent(&mut self, _user_agent: &str) -> Result<(), RestClientError> {
+        Ok(())
+    }
+
+    /// Logs the REST API calls that would have been made, then writes the comment.
+    async fn post_thread_comment(
+        &self,
+        options: ThreadCommentOptions,
+    ) -> Result<(), RestClientError> {
+        let marker = options.marker.trim();
+        log::info!("Would get the list of existing comments");
+        match options.policy {
+            CommentPolicy::Update => {
+                log::info!("Would delete all but the last comment marked with {marker}");
+                log::info!(
+                    "Would update the last comment marked with {marker} (or create a new comment if none exists)"
+                );
+            }
+            CommentPolicy::Anew => {
+                log::info!("Would delete all comments marked with {marker}");
+                log::info!("Would create a new comment");
+            }
+        }
+        self.write_comment(&options.mark_comment())
+    }
+
+    fn append_step_summary(&self, comment: &str) -> Result<(), RestClientError> {
+        log::info!("Would append the comment to the step summary");
+        self.write_comment(comment)
+    }
+
+    fn write_file_annotations(
+        &self,
+        annotations: &[FileAnnotation],
+    ) -> Result<(), RestClientError> {
+        for annotation in annotations {
+            log::info!(
+                "Would annotate {} ({:?}): {}",
+                annotation.path,
+                annotation.severity,
+                annotation.message
+            );
+        }
+        Ok(())
+    }
+
+    async fn cull_pr_reviews(
+        &mut self,
+        _options: &mut ReviewOptions,
+    ) -> Result<(), RestClientError> {
+        Ok(())
+    }
+
+    async fn post_pr_review(&mut self, _options: &ReviewOptions) -> Result<(), RestClientError> {
+        Ok(())
+    }
+
+    fn write_output_variables(&self, vars: &[OutputVariable]) -> Result<(), RestClientError> {
+        log_output_variables(vars)
+    }
+}
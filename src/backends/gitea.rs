@@ -0,0 +1,381 @@
+//! A module to post feedback via the REST API of Gitea (or Forgejo).
+//!
+//! Gitea and Forgejo Actions are compatible with GitHub Actions, so the CI context is
+//! read from the `GITHUB_*` environment variables that both set for workflow runs.
+//! Only the comment API is different from GitHub's REST API.
+use super::{
+    CommentPage, REPORT_FILE, api_base_url, append_report_file, log_output_variables,
+    remove_bot_comments,
+};
+use async_trait::async_trait;
+use git_bot_feedback::{
+    CommentPolicy, OutputVariable, RestApiClient, RestApiRateLimitHeaders, RestClientError,
+    ReviewOptions, ThreadCommentOptions, client::USER_AGENT,
+};
+use reqwest::{
+    Client, Method, Url,
+    header::{ACCEPT, AUTHORIZATION, HeaderMap, HeaderValue},
+};
+use serde::Deserialize;
+use std::{env, fs, path::PathBuf};
+
+/// A structure for deserializing a `pull_request` event's payload.
+#[derive(Debug, Deserialize)]
+struct PullRequestEvent {
+    pull_request: PullRequestInfo,
+}
+
+/// A structure for deserializing a Pull Request's info from an event's payload.
+#[derive(Debug, Deserialize)]
+struct PullRequestInfo {
+    number: u64,
+    #[serde(default)]
+    locked: bool,
+}
+
+/// A structure for deserializing an issue's comment from a response's JSON.
+#[derive(Debug, Deserialize)]
+struct IssueComment {
+    id: u64,
+    body: String,
+}
+
+/// A structure to work with Gitea (or Forgejo) REST API.
+pub struct GiteaApiClient {
+    /// The HTTP request client to be used for all REST API calls.
+    client: Client,
+
+    /// The base URL of the REST API (`/api/v1`).
+    api_url: Url,
+
+    /// The value of the `GITHUB_REPOSITORY` environment variable.
+    repo: String,
+
+    /// The Pull Request's number (if the workflow was triggered by a `pull_request` event).
+    pr_number: Option<u64>,
+
+    /// The token used to authenticate REST API requests.
+    token: Option<String>,
+
+    /// The value of the `ACTIONS_STEP_DEBUG` environment variable.
+    pub debug_enabled: bool,
+
+    /// The file that the report is written to when there is no step summary.
+    ///
+    /// This is only used if the `GITHUB_STEP_SUMMARY` environment variable is not set.
+    pub report_file: PathBuf,
+
+    /// The response header names that describe the rate limit status.
+    rate_limit_headers: RestApiRateLimitHeaders,
+}
+
+impl GiteaApiClient {
+    /// Instantiate a [`GiteaApiClient`] object.
+    ///
+    /// The given `api_url` should be the base URL of the REST API (eg. `https://codeberg.org/api/v1`).
+    /// The given `pr_number` should be [`None`] if the workflow was not triggered by a Pull Request.
+    pub fn new(
+        api_url: &str,
+        repo: String,
+        pr_number: Option<u64>,
+        token: Option<String>,
+    ) -> Result<Self, RestClientError> {
+        let api_url = api_base_url(api_url)?;
+        Ok(Self {
+            client: Client::builder()
+                .default_headers(Self::make_headers(token.as_deref())?)
+                .user_agent(USER_AGENT)
+                .build()?,
+            api_url,
+            repo,
+            pr_number,
+            token,
+            debug_enabled: false,
+            report_file: PathBuf::from(REPORT_FILE),
+            rate_limit_headers: RestApiRateLimitHeaders {
+                reset: "x-ratelimit-reset".to_string(),
+                remaining: "x-ratelimit-remaining".to_string(),
+                retry: "retry-after".to_string(),
+            },
+        })
+    }
+
+    /// Instantiate a [`GiteaApiClient`] object from the CI environment.
+    ///
+    /// The `GITEA_TOKEN` environment variable takes precedence over the given `token`.
+    pub fn from_env(token: Option<String>) -> Result<Self, RestClientError> {
+        let api_url = match env::var("GITHUB_API_URL") {
+            Ok(url) => url,
+            Err(_) => format!(
+                "{}/api/v1",
+                env::var("GITHUB_SERVER_URL")
+                    .map_err(|e| RestClientError::env_var("GITHUB_SERVER_URL", e))?
+                    .trim_end_matches('/')
+            ),
+        };
+        let repo = env::var("GITHUB_REPOSITORY")
+            .map_err(|e| RestClientError::env_var("GITHUB_REPOSITORY", e))?;
+        let pr_number =
+            if env::var("GITHUB_EVENT_NAME").is_ok_and(|v| v.starts_with("pull_request")) {
+                let event_path = env::var("GITHUB_EVENT_PATH")
+                    .map_err(|e| RestClientError::env_var("GITHUB_EVENT_PATH", e))?;
+                let payload = fs::read_to_string(&event_path)
+                    .map_err(|e| RestClientError::io("read event payload", e))?;
+                let event = serde_json::from_str::<PullRequestEvent>(&payload)
+                    .map_err(|e| RestClientError::json("deserialize event payload", e))?;
+                if event.pull_request.locked {
+                    log::info!("Cannot comment on a locked Pull Request");
+                    None
+                } else {
+                    Some(event.pull_request.number)
+                }
+            } else {
+                None
+            };
+        let token = env::var("GITEA_TOKEN").ok().or(token);
+        let mut client = Self::new(&api_url, repo, pr_number, token)?;
+        client.debug_enabled = env::var("ACTIONS_STEP_DEBUG").is_ok_and(|v| v == "true");
+        Ok(client)
+    }
+
+    fn make_headers(token: Option<&str>) -> Result<HeaderMap<HeaderValue>, RestClientError> {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+        headers.insert("Content-Type", HeaderValue::from_static("application/json"));
+        if let Some(token) = token {
+            let mut val = HeaderValue::from_str(format!("token {token}").as_str())?;
+            val.set_sensitive(true);
+            headers.insert(AUTHORIZATION, val);
+        } else {
+            log::warn!(
+                "No GITEA_TOKEN environment variable found! Permission to post comments may be unsatisfied."
+            );
+        }
+        Ok(headers)
+    }
+}
+
+#[async_trait]
+impl RestApiClient for GiteaApiClient {
+    fn client_kind(&self) -> String {
+        "gitea".to_string()
+    }
+
+    fn start_log_group(&self, name: &str) {
+        log::info!(target: "CI_LOG_GROUPING", "::group::{name}");
+    }
+
+    fn end_log_group(&self, _name: &str) {
+        log::info!(target: "CI_LOG_GROUPING", "::endgroup::");
+    }
+
+    fn is_pr_event(&self) -> bool {
+        self.pr_number.is_some()
+    }
+
+    fn is_debug_enabled(&self) -> bool {
+        self.debug_enabled
+    }
+
+    fn event_name(&self) -> Option<String> {
+        env::var("GITHUB_EVENT_NAME").ok()
+    }
+
+    fn set_user_agent(&mut self, user_agent: &str) -> Result<(), RestClientError> {
+        self.client = Client::builder()
+            .default_headers(Self::make_headers(self.token.as_deref())?)
+            .user_agent(user_agent)
+            .build()?;
+        Ok(())
+    }
+
+    /// Gitea only supports comments on Pull Requests (or issues), not on commits.
+    async fn post_thread_comment(
+        &self,
+        options: ThreadCommentOptions,
+    ) -> Result<(), RestClientError> {
+        let Some(pr_number) = self.pr_number else {
+            return Ok(());
+        };
+        let url = self
+            .api_url
+            .join(format!("repos/{}/issues/{pr_number}/comments", self.repo).as_str())?;
+        // Unlike listing comments, Gitea's API to edit or delete a comment is not specific to an issue.
+        let base_comment_url = self
+            .api_url
+            .join(format!("repos/{}/issues/comments/", self.repo).as_str())?;
+        let comment_url = remove_bot_comments(
+            self,
+            Url::parse_with_params(url.as_str(), &[("page", "1")])?,
+            &options.marker,
+            options.policy == CommentPolicy::Anew,
+            |url, method| async move {
+                let request = self.make_api_request(&self.client, url, method, None, None)?;
+                self.send_api_request(&self.client, request, &self.rate_limit_headers)
+                    .await
+            },
+            |headers, body| {
+                let comments = serde_json::from_str::<Vec<IssueComment>>(body).map_err(|e| {
+                    RestClientError::json("deserialize list of existing thread comments", e)
+                })?;
+                Ok(CommentPage {
+                    comments: comments.into_iter().map(|c| (c.id, c.body)).collect(),
+                    next: self.try_next_page(headers),
+                })
+            },
+            |id| Ok(base_comment_url.join(id.to_string().as_str())?),
+        )
+        .await?;
+        let payload = serde_json::json!({ "body": options.mark_comment() }).to_string();
+        let method = if comment_url.is_some() {
+            Method::PATCH
+        } else {
+            Method::POST
+        };
+        let request = self.make_api_request(
+            &self.client,
+            comment_url.unwrap_or(url),
+            method,
+            Some(payload),
+            None,
+        )?;
+        let response = self
+            .send_api_request(&self.client, request, &self.rate_limit_headers)
+            .await
+            .map_err(|e| e.add_request_context("post thread comment"))?;
+        self.log_response(response, "Failed to post thread comment")
+            .await;
+        Ok(())
+    }
+
+    /// Not all versions of Gitea (or Forgejo) support a step summary.
+    ///
+    /// If the `GITHUB_STEP_SUMMARY` environment variable is not set, then the
+    /// `comment` is appended to [`Self::report_file`] instead.
+    fn append_step_summary(&self, comment: &str) -> Result<(), RestClientError> {
+        let path = env::var("GITHUB_STEP_SUMMARY")
+            .map(PathBuf::from)
+            .unwrap_or(self.report_file.clone());
+        append_report_file(&path, comment)
+    }
+
+    async fn cull_pr_reviews(
+        &mut self,
+        _options: &mut ReviewOptions,
+    ) -> Result<(), RestClientError> {
+        Ok(())
+    }
+
+    async fn post_pr_review(&mut self, _options: &ReviewOptions) -> Result<(), RestClientError> {
+        Ok(())
+    }
+
+    fn write_output_variables(&self, vars: &[OutputVariable]) -> Result<(), RestClientError> {
+        log_output_variables(vars)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::GiteaApiClient;
+    use git_bot_feedback::{CommentPolicy, RestApiClient, ThreadCommentOptions};
+    use mockito::{Matcher, Server};
+
+    const REPO: &str = "2bndy5/arduino-report-size-deltas";
+    const PR: u64 = 22;
+    const TOKEN: &str = "123456";
+    const MARKER: &str = "<!-- marker -->\n";
+
+    const EXISTING_COMMENTS: &str = r#"[
+        {"id": 1, "body": "<!-- marker -->\nold report", "user": {"login": "bot", "id": 9}},
+        {"id": 3, "body": "a user's comment", "user": {"login": "user", "id": 8}},
+        {"id": 2, "body": "<!-- marker -->\nlatest report", "user": {"login": "bot", "id": 9}}
+    ]"#;
+
+    async fn post_comment(existing_comments: &str, policy: CommentPolicy) {
+        let mut server = Server::new_async().await;
+        let comments = format!("/api/v1/repos/{REPO}/issues/{PR}/comments");
+        let comment = format!("/api/v1/repos/{REPO}/issues/comments");
+        let mut mocks = vec![
+            server
+                .mock("GET", comments.as_str())
+                .match_query(Matcher::Any)
+                .match_header("Accept", "application/json")
+                .match_header("Authorization", format!("token {TOKEN}").as_str())
+                .with_body(existing_comments)
+                .create(),
+        ];
+        let payload = serde_json::json!({ "body": format!("{MARKER}report") });
+        let has_comments = existing_comments.contains(MARKER.trim_end());
+        if has_comments {
+            // the outdated comment is always deleted
+            mocks.push(
+                server
+                    .mock("DELETE", format!("{comment}/1").as_str())
+                    .with_status(204)
+                    .create(),
+            );
+        }
+        if has_comments && policy == CommentPolicy::Update {
+            mocks.push(
+                server
+                    .mock("PATCH", format!("{comment}/2").as_str())
+                    .match_body(Matcher::Json(payload))
+                    .create(),
+            );
+        } else {
+            if has_comments {
+                mocks.push(
+                    server
+                        .mock("DELETE", format!("{comment}/2").as_str())
+                        .with_status(204)
+                        .create(),
+                );
+            }
+            mocks.push(
+                server
+                    .mock("POST", comments.as_str())
+                    .match_header("Authorization", format!("token {TOKEN}").as_str())
+                    .match_body(Matcher::Json(payload))
+                    .with_status(201)
+                    .create(),
+            );
+        }
+
+        let client = GiteaApiClient::new(
+            format!("{}/api/v1", server.url()).as_str(),
+            REPO.to_string(),
+            Some(PR),
+            Some(TOKEN.to_string()),
+        )
+        .unwrap();
+        assert!(client.is_pr_event());
+        client
+            .post_thread_comment(ThreadCommentOptions {
+                comment: "report".to_string(),
+                marker: MARKER.to_string(),
+                policy,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        for mock in mocks {
+            mock.assert();
+        }
+    }
+
+    #[tokio::test]
+    async fn create_comment() {
+        post_comment("[]", CommentPolicy::Update).await;
+    }
+
+    #[tokio::test]
+    async fn update_comment() {
+        post_comment(EXISTING_COMMENTS, CommentPolicy::Update).await;
+    }
+
+    #[tokio::test]
+    async fn replace_comments() {
+        post_comment(EXISTING_COMMENTS, CommentPolicy::Anew).await;
+    }
+}
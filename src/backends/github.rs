@@ -0,0 +1,376 @@
+//! A module for feedback that is specific to GitHub's REST API.
+//!
+//! The [`git_bot_feedback`] client is still used for posting thread comments.
+use super::api_base_url;
+use anyhow::{Context, Result, anyhow};
+use arduino_report_size_deltas::Severity;
+use git_bot_feedback::{AnnotationLevel, FileAnnotation, client::USER_AGENT};
+use reqwest::{
+    Client, Method, Url,
+    header::{ACCEPT, AUTHORIZATION, HeaderMap, HeaderValue},
+};
+use serde::{Deserialize, Serialize};
+use std::{env, fs};
+
+/// The name of the check run created by this tool.
+pub const CHECK_RUN_NAME: &str = "report-size-deltas";
+
+/// The prefix of each commit status' context.
+///
+/// The board's FQBN is appended to this prefix.
+pub const STATUS_CONTEXT_PREFIX: &str = "size-deltas/";
+
+/// The maximum length of a commit status' description.
+///
+/// This limitation is imposed by GitHub REST API.
+const MAX_STATUS_DESCRIPTION_LEN: usize = 140;
+
+/// The maximum number of annotations accepted per check run request.
+///
+/// This limitation is imposed by GitHub REST API.
+const MAX_ANNOTATIONS: usize = 50;
+
+/// A structure to describe the parts of a `pull_request` event's payload that are used here.
+#[derive(Debug, Deserialize)]
+struct PullRequestEvent {
+    pull_request: PullRequestHead,
+}
+
+#[derive(Debug, Deserialize)]
+struct PullRequestHead {
+    head: CommitRef,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommitRef {
+    sha: String,
+}
+
+/// The check run's output as expected by GitHub REST API.
+#[derive(Debug, Serialize)]
+struct CheckRunOutput<'a> {
+    title: &'a str,
+    summary: &'a str,
+    annotations: Vec<CheckRunAnnotation<'a>>,
+}
+
+/// A check run's annotation as expected by GitHub REST API.
+#[derive(Debug, Serialize)]
+struct CheckRunAnnotation<'a> {
+    path: &'a str,
+    start_line: usize,
+    end_line: usize,
+    annotation_level: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<&'a str>,
+    message: &'a str,
+}
+
+impl<'a> From<&'a FileAnnotation> for CheckRunAnnotation<'a> {
+    fn from(value: &'a FileAnnotation) -> Self {
+        let start_line = value.start_line.unwrap_or(1);
+        Self {
+            path: &value.path,
+            start_line,
+            end_line: value.end_line.unwrap_or(start_line),
+            annotation_level: match value.severity {
+                AnnotationLevel::Error => "failure",
+                AnnotationLevel::Warning => "warning",
+                AnnotationLevel::Notice | AnnotationLevel::Debug => "notice",
+            },
+            title: value.title.as_deref(),
+            message: &value.message,
+        }
+    }
+}
+
+/// The payload used to create a check run.
+#[derive(Debug, Serialize)]
+struct CheckRunPayload<'a> {
+    name: &'a str,
+    head_sha: &'a str,
+    status: &'static str,
+    conclusion: &'static str,
+    output: CheckRunOutput<'a>,
+}
+
+/// The payload used to create a commit status.
+#[derive(Debug, Serialize)]
+struct CommitStatusPayload {
+    state: &'static str,
+    context: String,
+    description: String,
+}
+
+/// The parts of a created check run's response that are used here.
+#[derive(Debug, Deserialize)]
+struct CheckRunResponse {
+    id: u64,
+}
+
+/// Get the conclusion of a check run based on the most severe regression found (if any).
+pub fn check_run_conclusion(worst: Option<Severity>) -> &'static str {
+    match worst {
+        None => "success",
+        Some(Severity::Warning) => "neutral",
+        Some(Severity::Error) => "failure",
+    }
+}
+
+/// Get the state of a commit status based on the most severe regression found (if any).
+///
+/// Only [`Severity::Error`] fails a commit status.
+pub fn commit_status_state(worst: Option<Severity>) -> &'static str {
+    match worst {
+        Some(Severity::Error) => "failure",
+        _ => "success",
+    }
+}
+
+/// A structure to work with the parts of GitHub REST API that [`git_bot_feedback`] does not cover.
+pub struct GithubContext {
+    /// The HTTP request client to be used for all REST API calls.
+    client: Client,
+
+    /// The value of the `GITHUB_API_URL` environment variable (with a trailing slash).
+    api_url: Url,
+
+    /// The value of the `GITHUB_REPOSITORY` environment variable.
+    repo: String,
+
+    /// The SHA of the commit that feedback is given about.
+    ///
+    /// For `pull_request` events, this is the SHA of the Pull Request's head commit
+    /// because `GITHUB_SHA` refers to a merge commit that is not shown in the Pull Request.
+    /// Otherwise, this is the value of the `GITHUB_SHA` environment variable.
+    pub sha: String,
+}
+
+impl GithubContext {
+    /// Instantiate a [`GithubContext`] from the CI environment and the given `token`.
+    pub fn new(token: Option<&str>) -> Result<Self> {
+        let api_url = env::var("GITHUB_API_URL").unwrap_or("https://api.github.com".to_string());
+        let repo = env::var("GITHUB_REPOSITORY").with_context(|| "GITHUB_REPOSITORY is not set")?;
+        Self::from_api(&api_url, repo, Self::head_sha()?, token)
+    }
+
+    /// Instantiate a [`GithubContext`] for the given `repo` (`owner/name`) and commit `sha`.
+    ///
+    /// The given `api_url` should be the base URL of GitHub's REST API
+    /// (for example, `https://ghe.example.com/api/v3` for GitHub Enterprise Server).
+    pub fn from_api(api_url: &str, repo: String, sha: String, token: Option<&str>) -> Result<Self> {
+        let api_url = api_base_url(api_url)?;
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            ACCEPT,
+            HeaderValue::from_static("application/vnd.github+json"),
+        );
+        if let Some(token) = token {
+            let mut val = HeaderValue::from_str(format!("token {token}").as_str())?;
+            val.set_sensitive(true);
+            headers.insert(AUTHORIZATION, val);
+        } else {
+            log::warn!("No GitHub token given! Permission to give feedback may be unsatisfied.");
+        }
+        Ok(Self {
+            client: Client::builder()
+                .default_headers(headers)
+                .user_agent(USER_AGENT)
+                .build()?,
+            api_url,
+            repo,
+            sha,
+        })
+    }
+
+    /// Get the SHA of the commit that feedback should be attached to.
+    pub fn head_sha() -> Result<String> {
+        if env::var("GITHUB_EVENT_NAME").is_ok_and(|v| v.starts_with("pull_request"))
+            && let Ok(event_path) = env::var("GITHUB_EVENT_PATH")
+            && let Ok(payload) = fs::read_to_string(event_path)
+            && let Ok(event) = serde_json::from_str::<PullRequestEvent>(&payload)
+        {
+            return Ok(event.pull_request.head.sha);
+        }
+        env::var("GITHUB_SHA").with_context(|| "GITHUB_SHA is not set")
+    }
+
+    /// Send a request with an optional JSON `payload` and return the response's body.
+    async fn send(&self, method: Method, url: Url, payload: Option<String>) -> Result<String> {
+        let mut request = self.client.request(method, url);
+        if let Some(payload) = payload {
+            request = request.body(payload);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(anyhow!("{status}: {body}"));
+        }
+        Ok(body)
+    }
+
+    /// Create a completed check run with the given `summary` (in markdown) and `annotations`.
+    ///
+    /// GitHub only accepts 50 annotations per request.
+    /// Any additional annotations are added by updating the created check run.
+    pub async fn create_check_run(
+        &self,
+        title: &str,
+        summary: &str,
+        conclusion: &'static str,
+        annotations: &[FileAnnotation],
+    ) -> Result<()> {
+        let mut chunks = annotations.chunks(MAX_ANNOTATIONS);
+        let payload = CheckRunPayload {
+            name: CHECK_RUN_NAME,
+            head_sha: &self.sha,
+            status: "completed",
+            conclusion,
+            output: CheckRunOutput {
+                title,
+                summary,
+                annotations: chunks
+                    .next()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(CheckRunAnnotation::from)
+                    .collect(),
+            },
+        };
+        let url = self
+            .api_url
+            .join(format!("repos/{}/check-runs", self.repo).as_str())?;
+        let body = self
+            .send(
+                Method::POST,
+                url.clone(),
+                Some(serde_json::to_string(&payload)?),
+            )
+            .await
+            .with_context(|| "Failed to create check run")?;
+        let check_run = serde_json::from_str::<CheckRunResponse>(&body)?;
+
+        let url = self
+            .api_url
+            .join(format!("repos/{}/check-runs/{}", self.repo, check_run.id).as_str())?;
+        for chunk in chunks {
+            let output = CheckRunOutput {
+                title,
+                summary,
+                annotations: chunk.iter().map(CheckRunAnnotation::from).collect(),
+            };
+            self.send(
+                Method::PATCH,
+                url.clone(),
+                Some(serde_json::json!({ "output": output }).to_string()),
+            )
+            .await
+            .with_context(|| "Failed to add annotations to check run")?;
+        }
+        Ok(())
+    }
+
+    /// Create a commit status about the given `board` with the given `state` and `description`.
+    ///
+    /// The `description` is truncated to satisfy GitHub's length limit.
+    pub async fn create_commit_status(
+        &self,
+        board: &str,
+        state: &'static str,
+        description: &str,
+    ) -> Result<()> {
+        let payload = CommitStatusPayload {
+            state,
+            context: format!("{STATUS_CONTEXT_PREFIX}{board}"),
+            description: description
+                .chars()
+                .take(MAX_STATUS_DESCRIPTION_LEN)
+                .collect(),
+        };
+        let url = self
+            .api_url
+            .join(format!("repos/{}/statuses/{}", self.repo, self.sha).as_str())?;
+        self.send(Method::POST, url, Some(serde_json::to_string(&payload)?))
+            .await
+            .with_context(|| format!("Failed to create commit status for {board}"))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::GithubContext;
+    use git_bot_feedback::{AnnotationLevel, FileAnnotation};
+    use mockito::{Matcher, Server};
+
+    const REPO: &str = "2bndy5/arduino-report-size-deltas";
+    const SHA: &str = "deadbeef";
+    const TOKEN: &str = "ghp_123456";
+
+    fn annotation(index: usize) -> FileAnnotation {
+        FileAnnotation {
+            severity: AnnotationLevel::Warning,
+            path: format!("examples/Sketch{index}/Sketch{index}.ino"),
+            message: "Increased flash usage".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Get a matcher for a JSON payload with exactly the given number of annotations.
+    fn annotations(count: usize) -> Matcher {
+        Matcher::Regex(format!(r#""annotations":\[(\{{[^}}]*\}},?){{{count}}}\]"#))
+    }
+
+    /// GitHub Enterprise Server's REST API is served from a path (`/api/v3`).
+    #[tokio::test]
+    async fn enterprise_api_url() {
+        let mut server = Server::new_async().await;
+        let base = format!("{}/api/v3", server.url());
+        let statuses = format!("/api/v3/repos/{REPO}/statuses/{SHA}");
+        let mock = server
+            .mock("POST", statuses.as_str())
+            .match_header("Authorization", format!("token {TOKEN}").as_str())
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "state": "success",
+                "context": "size-deltas/arduino:avr:uno",
+            })))
+            .create();
+        let context =
+            GithubContext::from_api(&base, REPO.to_string(), SHA.to_string(), Some(TOKEN)).unwrap();
+        context
+            .create_commit_status("arduino:avr:uno", "success", "No change")
+            .await
+            .unwrap();
+        mock.assert();
+    }
+
+    /// Annotations beyond the first 50 are added by updating the created check run.
+    #[tokio::test]
+    async fn many_annotations() {
+        let mut server = Server::new_async().await;
+        let base = format!("{}/api/v3/", server.url());
+        let check_runs = format!("/api/v3/repos/{REPO}/check-runs");
+        let mocks = [
+            server
+                .mock("POST", check_runs.as_str())
+                .match_body(annotations(50))
+                .with_body(r#"{"id": 42}"#)
+                .create(),
+            server
+                .mock("PATCH", format!("{check_runs}/42").as_str())
+                .match_body(annotations(1))
+                .create(),
+        ];
+        let context =
+            GithubContext::from_api(&base, REPO.to_string(), SHA.to_string(), None).unwrap();
+        let annotations = (0..51).map(annotation).collect::<Vec<_>>();
+        context
+            .create_check_run("1 size regression(s)", "summary", "neutral", &annotations)
+            .await
+            .unwrap();
+        for mock in mocks {
+            mock.assert();
+        }
+    }
+}
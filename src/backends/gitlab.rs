@@ -0,0 +1,391 @@
+//! A module to post feedback via GitLab's REST API.
+//!
+//! This implements [`RestApiClient`] so the posting path in `run()` is the same for all Git servers.
+use super::{
+    CommentPage, REPORT_FILE, api_base_url, append_report_file, log_output_variables,
+    remove_bot_comments,
+};
+use async_trait::async_trait;
+use git_bot_feedback::{
+    CommentPolicy, OutputVariable, RestApiClient, RestApiRateLimitHeaders, RestClientError,
+    ReviewOptions, ThreadCommentOptions, client::USER_AGENT,
+};
+use reqwest::{
+    Client, Method, Url,
+    header::{HeaderMap, HeaderValue},
+};
+use serde::Deserialize;
+use std::{
+    env,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// A structure for deserializing a merge request's note from a response's JSON.
+#[derive(Debug, Deserialize)]
+struct Note {
+    id: u64,
+    body: String,
+}
+
+/// A structure to work with GitLab REST API.
+pub struct GitlabApiClient {
+    /// The HTTP request client to be used for all REST API calls.
+    client: Client,
+
+    /// The value of the `CI_API_V4_URL` environment variable.
+    api_url: Url,
+
+    /// The value of the `CI_PROJECT_ID` environment variable.
+    project_id: String,
+
+    /// The value of the `CI_MERGE_REQUEST_IID` environment variable.
+    ///
+    /// This is [`None`] for pipelines that do not run for a merge request.
+    merge_request_iid: Option<u64>,
+
+    /// The token used to authenticate REST API requests.
+    token: Option<String>,
+
+    /// The value of the `CI_DEBUG_TRACE` environment variable.
+    pub debug_enabled: bool,
+
+    /// The file that the report is written to when not running for a merge request.
+    pub report_file: PathBuf,
+
+    /// The response header names that describe the rate limit status.
+    rate_limit_headers: RestApiRateLimitHeaders,
+}
+
+impl GitlabApiClient {
+    /// Instantiate a [`GitlabApiClient`] object.
+    ///
+    /// The given `api_url` should be the base URL of GitLab's REST API (v4).
+    pub fn new(
+        api_url: &str,
+        project_id: String,
+        merge_request_iid: Option<u64>,
+        token: Option<String>,
+    ) -> Result<Self, RestClientError> {
+        let api_url = api_base_url(api_url)?;
+        Ok(Self {
+            client: Client::builder()
+                .default_headers(Self::make_headers(token.as_deref())?)
+                .user_agent(USER_AGENT)
+                .build()?,
+            api_url,
+            project_id,
+            merge_request_iid,
+            token,
+            debug_enabled: false,
+            report_file: PathBuf::from(REPORT_FILE),
+            rate_limit_headers: RestApiRateLimitHeaders {
+                reset: "ratelimit-reset".to_string(),
+                remaining: "ratelimit-remaining".to_string(),
+                retry: "retry-after".to_string(),
+            },
+        })
+    }
+
+    /// Instantiate a [`GitlabApiClient`] object from GitLab CI's predefined environment variables.
+    ///
+    /// The `GITLAB_TOKEN` environment variable takes precedence over the given `token`.
+    pub fn from_env(token: Option<String>) -> Result<Self, RestClientError> {
+        let api_url =
+            env::var("CI_API_V4_URL").map_err(|e| RestClientError::env_var("CI_API_V4_URL", e))?;
+        let project_id =
+            env::var("CI_PROJECT_ID").map_err(|e| RestClientError::env_var("CI_PROJECT_ID", e))?;
+        let merge_request_iid = match env::var("CI_MERGE_REQUEST_IID") {
+            Ok(iid) => Some(iid.parse::<u64>().map_err(|_| {
+                RestClientError::MalformedEventInfo(format!("CI_MERGE_REQUEST_IID={iid}"))
+            })?),
+            Err(_) => None,
+        };
+        let token = env::var("GITLAB_TOKEN").ok().or(token);
+        let mut client = Self::new(&api_url, project_id, merge_request_iid, token)?;
+        client.debug_enabled = env::var("CI_DEBUG_TRACE").is_ok_and(|v| v == "true");
+        Ok(client)
+    }
+
+    fn make_headers(token: Option<&str>) -> Result<HeaderMap<HeaderValue>, RestClientError> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", HeaderValue::from_static("application/json"));
+        if let Some(token) = token {
+            let mut val = HeaderValue::from_str(token)?;
+            val.set_sensitive(true);
+            headers.insert("PRIVATE-TOKEN", val);
+        } else {
+            log::warn!(
+                "No GITLAB_TOKEN environment variable found! Permission to post comments may be unsatisfied."
+            );
+        }
+        Ok(headers)
+    }
+
+    /// Get the URL of the merge request's notes.
+    fn notes_url(&self, merge_request_iid: u64) -> Result<Url, RestClientError> {
+        Ok(self.api_url.join(
+            format!(
+                "projects/{}/merge_requests/{merge_request_iid}/notes",
+                self.project_id
+            )
+            .as_str(),
+        )?)
+    }
+}
+
+#[async_trait]
+impl RestApiClient for GitlabApiClient {
+    fn client_kind(&self) -> String {
+        "gitlab".to_string()
+    }
+
+    fn start_log_group(&self, name: &str) {
+        let section = name.replace(|c: char| !c.is_ascii_alphanumeric(), "_");
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        log::info!(
+            target: "CI_LOG_GROUPING",
+            "\x1b[0Ksection_start:{timestamp}:{section}[collapsed=true]\r\x1b[0K{name}"
+        );
+    }
+
+    fn end_log_group(&self, name: &str) {
+        let section = name.replace(|c: char| !c.is_ascii_alphanumeric(), "_");
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        log::info!(
+            target: "CI_LOG_GROUPING",
+            "\x1b[0Ksection_end:{timestamp}:{section}\r\x1b[0K"
+        );
+    }
+
+    fn is_pr_event(&self) -> bool {
+        self.merge_request_iid.is_some()
+    }
+
+    fn is_debug_enabled(&self) -> bool {
+        self.debug_enabled
+    }
+
+    fn event_name(&self) -> Option<String> {
+        env::var("CI_PIPELINE_SOURCE").ok()
+    }
+
+    fn set_user_agent(&mut self, user_agent: &str) -> Result<(), RestClientError> {
+        self.client = Client::builder()
+            .default_headers(Self::make_headers(self.token.as_deref())?)
+            .user_agent(user_agent)
+            .build()?;
+        Ok(())
+    }
+
+    /// Does not support pipelines that are not for a merge request.
+    async fn post_thread_comment(
+        &self,
+        options: ThreadCommentOptions,
+    ) -> Result<(), RestClientError> {
+        let Some(merge_request_iid) = self.merge_request_iid else {
+            return Ok(());
+        };
+        let url = self.notes_url(merge_request_iid)?;
+        // GitLab lists the newest notes first by default
+        let first_page = Url::parse_with_params(
+            url.as_str(),
+            &[
+                ("sort", "asc"),
+                ("order_by", "created_at"),
+                ("per_page", "100"),
+                ("page", "1"),
+            ],
+        )?;
+        let note_url = remove_bot_comments(
+            self,
+            first_page,
+            &options.marker,
+            options.policy == CommentPolicy::Anew,
+            |url, method| async move {
+                let request = self.make_api_request(&self.client, url, method, None, None)?;
+                self.send_api_request(&self.client, request, &self.rate_limit_headers)
+                    .await
+            },
+            |headers, body| {
+                let notes = serde_json::from_str::<Vec<Note>>(body)
+                    .map_err(|e| RestClientError::json("deserialize list of existing notes", e))?;
+                Ok(CommentPage {
+                    comments: notes.into_iter().map(|n| (n.id, n.body)).collect(),
+                    next: self.try_next_page(headers),
+                })
+            },
+            |id| Ok(Url::parse(format!("{url}/{id}").as_str())?),
+        )
+        .await?;
+        let payload = serde_json::json!({ "body": options.mark_comment() }).to_string();
+        let method = if note_url.is_some() {
+            Method::PUT
+        } else {
+            Method::POST
+        };
+        let request = self.make_api_request(
+            &self.client,
+            note_url.unwrap_or(url),
+            method,
+            Some(payload),
+            None,
+        )?;
+        let response = self
+            .send_api_request(&self.client, request, &self.rate_limit_headers)
+            .await
+            .map_err(|e| e.add_request_context("post note"))?;
+        self.log_response(response, "Failed to post note").await;
+        Ok(())
+    }
+
+    /// GitLab has no step summary, so the `comment` is appended to [`Self::report_file`] instead.
+    fn append_step_summary(&self, comment: &str) -> Result<(), RestClientError> {
+        append_report_file(&self.report_file, comment)
+    }
+
+    async fn cull_pr_reviews(
+        &mut self,
+        _options: &mut ReviewOptions,
+    ) -> Result<(), RestClientError> {
+        Ok(())
+    }
+
+    async fn post_pr_review(&mut self, _options: &ReviewOptions) -> Result<(), RestClientError> {
+        Ok(())
+    }
+
+    fn write_output_variables(&self, vars: &[OutputVariable]) -> Result<(), RestClientError> {
+        log_output_variables(vars)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::GitlabApiClient;
+    use git_bot_feedback::{CommentPolicy, RestApiClient, ThreadCommentOptions};
+    use mockito::{Matcher, Server};
+    use std::fs;
+    use tempfile::NamedTempFile;
+
+    const PROJECT: &str = "42";
+    const MR: u64 = 7;
+    const TOKEN: &str = "glpat-123456";
+    const MARKER: &str = "<!-- marker -->\n";
+
+    fn options(policy: CommentPolicy) -> ThreadCommentOptions {
+        ThreadCommentOptions {
+            comment: "report".to_string(),
+            marker: MARKER.to_string(),
+            policy,
+            ..Default::default()
+        }
+    }
+
+    async fn post_note(existing_notes: &str, policy: CommentPolicy) {
+        let mut server = Server::new_async().await;
+        let notes = format!("/api/v4/projects/{PROJECT}/merge_requests/{MR}/notes");
+        let mut mocks = vec![
+            server
+                .mock("GET", notes.as_str())
+                .match_query(Matcher::AllOf(vec![
+                    Matcher::UrlEncoded("sort".to_string(), "asc".to_string()),
+                    Matcher::UrlEncoded("order_by".to_string(), "created_at".to_string()),
+                ]))
+                .match_header("PRIVATE-TOKEN", TOKEN)
+                .with_body(existing_notes)
+                .create(),
+        ];
+        let payload = serde_json::json!({ "body": format!("{MARKER}report") });
+        let has_notes = existing_notes.contains(MARKER.trim_end());
+        if has_notes {
+            // the outdated note is always deleted
+            mocks.push(
+                server
+                    .mock("DELETE", format!("{notes}/1").as_str())
+                    .create(),
+            );
+        }
+        if has_notes && policy == CommentPolicy::Update {
+            mocks.push(
+                server
+                    .mock("PUT", format!("{notes}/2").as_str())
+                    .match_body(Matcher::Json(payload))
+                    .create(),
+            );
+        } else {
+            if has_notes {
+                mocks.push(
+                    server
+                        .mock("DELETE", format!("{notes}/2").as_str())
+                        .create(),
+                );
+            }
+            mocks.push(
+                server
+                    .mock("POST", notes.as_str())
+                    .match_header("PRIVATE-TOKEN", TOKEN)
+                    .match_body(Matcher::Json(payload))
+                    .with_status(201)
+                    .create(),
+            );
+        }
+
+        let client = GitlabApiClient::new(
+            format!("{}/api/v4", server.url()).as_str(),
+            PROJECT.to_string(),
+            Some(MR),
+            Some(TOKEN.to_string()),
+        )
+        .unwrap();
+        assert!(client.is_pr_event());
+        client.post_thread_comment(options(policy)).await.unwrap();
+        for mock in mocks {
+            mock.assert();
+        }
+    }
+
+    const EXISTING_NOTES: &str = r#"[
+        {"id": 1, "body": "<!-- marker -->\nold report"},
+        {"id": 3, "body": "a user's note"},
+        {"id": 2, "body": "<!-- marker -->\nlatest report"}
+    ]"#;
+
+    #[tokio::test]
+    async fn create_note() {
+        post_note("[]", CommentPolicy::Update).await;
+    }
+
+    #[tokio::test]
+    async fn update_note() {
+        post_note(EXISTING_NOTES, CommentPolicy::Update).await;
+    }
+
+    #[tokio::test]
+    async fn replace_notes() {
+        post_note(EXISTING_NOTES, CommentPolicy::Anew).await;
+    }
+
+    #[tokio::test]
+    async fn not_a_merge_request() {
+        let report_file = NamedTempFile::new().unwrap();
+        let mut client =
+            GitlabApiClient::new("http://localhost/api/v4", PROJECT.to_string(), None, None)
+                .unwrap();
+        client.report_file = report_file.path().to_path_buf();
+        assert!(!client.is_pr_event());
+        // no HTTP requests are made here
+        client
+            .post_thread_comment(options(CommentPolicy::Update))
+            .await
+            .unwrap();
+        client.append_step_summary("report").unwrap();
+        assert_eq!(fs::read_to_string(report_file).unwrap(), "\nreport\n\n");
+    }
+}
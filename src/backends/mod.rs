@@ -0,0 +1,184 @@
+//! A module to hold the feedback mechanisms that are not provided by [`git_bot_feedback`].
+use git_bot_feedback::{OutputVariable, RestApiClient, RestClientError};
+use reqwest::{Method, Response, Url, header::HeaderMap};
+use std::{env, fs::OpenOptions, io::Write, path::Path};
+pub mod bitbucket;
+pub mod dry_run;
+pub mod gitea;
+pub mod github;
+pub mod gitlab;
+use bitbucket::BitbucketApiClient;
+use gitea::GiteaApiClient;
+use gitlab::GitlabApiClient;
+
+/// The default file that a report is written to if the CI platform has no step summary.
+pub const REPORT_FILE: &str = "size-deltas-report.md";
+
+/// The git server (and its CI platform) that feedback is given to.
+#[derive(clap::ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GitServer {
+    /// Detect the git server from the CI environment
+    #[default]
+    Auto,
+
+    /// GitHub (and GitHub Actions)
+    Github,
+
+    /// Gitea or Forgejo (and their Actions)
+    Gitea,
+
+    /// GitLab (and GitLab CI)
+    Gitlab,
+
+    /// Bitbucket Cloud (and Bitbucket Pipelines)
+    Bitbucket,
+}
+
+impl GitServer {
+    /// Resolve [`GitServer::Auto`] into a specific git server based on the environment.
+    ///
+    /// GitLab CI is detected by the `GITLAB_CI` environment variable.
+    /// Gitea and Forgejo Actions are detected by the `GITEA_ACTIONS` or `FORGEJO_ACTIONS`
+    /// environment variable. Bitbucket Pipelines is detected by the `BITBUCKET_BUILD_NUMBER`
+    /// environment variable. Otherwise, GitHub is assumed.
+    pub fn resolve(self) -> Self {
+        if self != GitServer::Auto {
+            return self;
+        }
+        let is_set = |name: &str| env::var(name).is_ok_and(|v| v.to_lowercase() == "true");
+        if is_set("GITLAB_CI") {
+            GitServer::Gitlab
+        } else if is_set("GITEA_ACTIONS") || is_set("FORGEJO_ACTIONS") {
+            GitServer::Gitea
+        } else if env::var("BITBUCKET_BUILD_NUMBER").is_ok() {
+            GitServer::Bitbucket
+        } else {
+            GitServer::Github
+        }
+    }
+}
+
+/// Instantiate an implementation of [`RestApiClient`] for the given `server`.
+///
+/// This extends [`git_bot_feedback::client::init_client()`] with support for GitLab CI,
+/// Gitea (or Forgejo) Actions and Bitbucket Pipelines.
+/// The given `token` is only used for GitLab or Gitea if the `GITLAB_TOKEN` or
+/// `GITEA_TOKEN` environment variable (respectively) is not set.
+/// For Bitbucket, the given `token` is only used if no app password is set.
+pub fn init_client(
+    server: GitServer,
+    token: Option<String>,
+) -> Result<Box<dyn RestApiClient + Send + Sync>, RestClientError> {
+    match server.resolve() {
+        GitServer::Gitlab => Ok(Box::new(GitlabApiClient::from_env(token)?)),
+        GitServer::Gitea => Ok(Box::new(GiteaApiClient::from_env(token)?)),
+        GitServer::Bitbucket => Ok(Box::new(BitbucketApiClient::from_env(token)?)),
+        _ => git_bot_feedback::client::init_client(),
+    }
+}
+
+/// Append the given `comment` to the file at the given `path`.
+///
+/// This is used by backends for CI platforms that have no step summary.
+fn append_report_file(path: &Path, comment: &str) -> Result<(), RestClientError> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| RestClientError::io("open report file", e))?;
+    writeln!(&mut file, "\n{comment}\n")
+        .map_err(|e| RestClientError::io("write to report file", e))?;
+    log::info!("Wrote report to {}", path.to_string_lossy());
+    Ok(())
+}
+
+/// Parse the base URL of a git server's REST API.
+///
+/// The parsed URL always ends with a slash, so that paths joined to it are appended
+/// (instead of replacing the last path segment).
+fn api_base_url(url: &str) -> Result<Url, RestClientError> {
+    Ok(Url::parse(
+        format!("{}/", url.trim_end_matches('/')).as_str(),
+    )?)
+}
+
+/// A page of comments listed via a git server's REST API.
+struct CommentPage {
+    /// The ID and body of each comment (oldest first).
+    comments: Vec<(u64, String)>,
+
+    /// The URL of the next page (if any).
+    next: Option<Url>,
+}
+
+/// Get the URL of the last comment that starts with the given `marker`.
+///
+/// The comments are listed starting at the `first_page` (oldest first),
+/// and each page's response (headers and body) is parsed with `parse_page`.
+/// All other comments with the given `marker` are deleted.
+/// If `delete` is `true`, then the last comment with the given `marker` is also deleted.
+///
+/// All requests are made with `send`, and each comment's URL is given by `comment_url`.
+async fn remove_bot_comments<C, F>(
+    client: &C,
+    first_page: Url,
+    marker: &str,
+    delete: bool,
+    send: impl Fn(Url, Method) -> F,
+    parse_page: impl Fn(&HeaderMap, &str) -> Result<CommentPage, RestClientError>,
+    comment_url: impl Fn(u64) -> Result<Url, RestClientError>,
+) -> Result<Option<Url>, RestClientError>
+where
+    C: RestApiClient + Sync + ?Sized,
+    F: Future<Output = Result<Response, RestClientError>>,
+{
+    let mut last_url: Option<Url> = None;
+    let mut page_url = Some(first_page);
+    while let Some(endpoint) = page_url.take() {
+        let response = send(endpoint, Method::GET)
+            .await
+            .map_err(|e| e.add_request_context("get list of existing comments"))?;
+        if !response.status().is_success() {
+            client
+                .log_response(response, "Failed to get list of existing comments")
+                .await;
+            return Ok(last_url);
+        }
+        let headers = response.headers().clone();
+        let page = parse_page(&headers, &response.text().await?)?;
+        page_url = page.next;
+        for (id, _) in page
+            .comments
+            .into_iter()
+            .filter(|(_, body)| body.starts_with(marker))
+        {
+            log::debug!("Found bot comment id {id}");
+            let this_url = comment_url(id)?;
+            if delete || last_url.is_some() {
+                // if not updating: remove all outdated comments
+                // if updating: remove all outdated comments except the last one
+                let del_url = last_url.as_ref().unwrap_or(&this_url).clone();
+                let response = send(del_url, Method::DELETE)
+                    .await
+                    .map_err(|e| e.add_request_context("delete old comment"))?;
+                client
+                    .log_response(response, "Failed to delete old comment")
+                    .await;
+            }
+            if !delete {
+                last_url = Some(this_url);
+            }
+        }
+    }
+    Ok(last_url)
+}
+
+/// Log the given output variables (see [`RestApiClient::write_output_variables()`]).
+///
+/// This tool does not use output variables, so they are only logged.
+fn log_output_variables(vars: &[OutputVariable]) -> Result<(), RestClientError> {
+    for var in vars {
+        log::info!("{}: {}", var.name, var.value);
+    }
+    Ok(())
+}
@@ -12,6 +12,26 @@ pub enum JsonError {
     FileReadFail(#[from] std::io::Error),
 }
 
+/// The possible error types related to parsing ELF files.
+#[derive(Debug, Error)]
+pub enum ElfError {
+    /// Error emitted when failing to read an ELF file.
+    #[error("{0:?}")]
+    FileReadFail(#[from] std::io::Error),
+
+    /// Error emitted when the file does not start with the ELF magic number.
+    #[error("Not an ELF file")]
+    NotElf,
+
+    /// Error emitted when the ELF file uses an unsupported encoding.
+    #[error("Unsupported ELF {0}")]
+    Unsupported(&'static str),
+
+    /// Error emitted when a header or section header extends beyond the end of the file.
+    #[error("Truncated ELF file")]
+    Truncated,
+}
+
 /// The types of error that could be propagated from [`generate_comment()`][fn@crate::summarize::generate_comment].
 #[derive(Debug, Error)]
 pub enum CommentAssemblyError {
@@ -23,6 +43,14 @@ pub enum CommentAssemblyError {
     #[error("{0:?}")]
     Json(#[from] JsonError),
 
+    /// Represents any [`ElfError`] encountered.
+    #[error("{0:?}")]
+    Elf(#[from] ElfError),
+
+    /// Represents an invalid glob pattern used to discover report files.
+    #[error("{0}")]
+    Pattern(#[from] globset::Error),
+
     /// Represents an error in which expected data is not found.
     ///
     /// Check stderr for the root cause of this kind of error because
@@ -30,3 +58,17 @@ pub enum CommentAssemblyError {
     #[error("Found no applicable data to summarize")]
     NotFound,
 }
+
+/// The types of error that could be propagated from a
+/// [`TemplateRenderer`](crate::TemplateRenderer) (requires the `template` feature).
+#[cfg(feature = "template")]
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    /// Represents a failure to read the template file.
+    #[error("{0:?}")]
+    Io(#[from] std::io::Error),
+
+    /// Represents a syntax error in the template or a failure to render it.
+    #[error("{0:#}")]
+    Render(#[from] minijinja::Error),
+}
@@ -3,8 +3,30 @@
 
 mod error;
 mod reports;
+#[cfg(feature = "schema")]
+pub mod schema;
 pub use reports::structs as report_structs;
 mod summarize;
-pub use error::{CommentAssemblyError, JsonError};
-pub use reports::parse_artifacts;
-pub use summarize::{COMMENT_MARKER, generate_comment};
+#[cfg(feature = "template")]
+pub use error::TemplateError;
+pub use error::{CommentAssemblyError, ElfError, JsonError};
+pub use reports::{
+    DEFAULT_INCLUDE, DiscoveryOptions, ReportFormat, parse_arduino_cli, parse_artifacts,
+    parse_artifacts_with, parse_elf, parse_platformio, parse_reader, parse_report,
+};
+pub use summarize::model as summary_structs;
+pub use summarize::regressions::{
+    BoardStatus, MemoryKind, Regression, Severity, Thresholds, board_statuses, find_regressions,
+};
+#[cfg(feature = "terminal")]
+pub use summarize::render::TerminalRenderer;
+pub use summarize::render::{
+    COMMENT_MARKER, HtmlRenderer, HtmlReportRenderer, JsonRenderer, MarkdownRenderer,
+    PlainTextRenderer, Renderer,
+};
+#[cfg(feature = "template")]
+pub use summarize::render::{DEFAULT_TEMPLATE, TemplateRenderer};
+pub use summarize::{
+    RenderOptions, RenderedReports, assemble_comment, generate_comment, load_reports,
+    load_reports_from_reader, load_reports_with, render_reports,
+};
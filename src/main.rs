@@ -1,22 +1,45 @@
 //! This is the main CLI application code.
 //! Use `cargo run [-- <Args>...]` to run this locally from source.
 use anyhow::{Context, Result, anyhow};
-use arduino_report_size_deltas::{COMMENT_MARKER, generate_comment};
-use clap::Parser;
+use arduino_report_size_deltas::{
+    COMMENT_MARKER, CommentAssemblyError, DEFAULT_INCLUDE, DiscoveryOptions, HtmlRenderer,
+    HtmlReportRenderer, JsonRenderer, MarkdownRenderer, PlainTextRenderer, Regression, Renderer,
+    Severity, TemplateRenderer, TerminalRenderer, Thresholds, board_statuses, find_regressions,
+    load_reports_from_reader, load_reports_with, parse_arduino_cli, parse_elf, parse_platformio,
+    report_structs::Report,
+    schema::{SCHEMA_VERSION, validate_artifacts, validate_reader},
+    summary_structs::{ByteUnits, SizeFormat, Summary, SymbolSet},
+};
+use backends::{
+    GitServer,
+    dry_run::DryRunClient,
+    github::{GithubContext, check_run_conclusion, commit_status_state},
+    init_client,
+};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
 use colored::Colorize;
-use git_bot_feedback::{CommentPolicy, ThreadCommentOptions, client::init_client};
+use git_bot_feedback::{
+    AnnotationLevel, CommentPolicy, FileAnnotation, RestApiClient, ThreadCommentOptions,
+};
 use log::{Level, LevelFilter, Metadata, Record};
 use std::{
-    env,
-    io::{Write, stdout},
+    env, fs,
+    io::{Read, Write, stderr, stdin, stdout},
     path::PathBuf,
 };
+mod backends;
+
+/// The [`Args::sketches_reports_source`] used to read the reports from stdin.
+const STDIN_SOURCE: &str = "-";
 
 /// This is a CI tool to compliment the artifacts created by arduino/compile-sketches action.
 #[derive(Parser, Debug)]
 #[command(name = "report-size-deltas", version, about, long_about)]
 pub struct Args {
     /// The path to the folder containing sketches' reports (JSON files)
+    ///
+    /// Use `-` to read the reports from stdin (a single report,
+    /// a JSON array of reports, or newline-delimited JSON).
     #[arg(
         short,
         long,
@@ -25,9 +48,446 @@ pub struct Args {
     )]
     sketches_reports_source: PathBuf,
 
+    /// The glob patterns of the reports to include (separated by commas)
+    ///
+    /// Patterns are relative to the `--sketches-reports-source` folder.
+    #[arg(
+        long,
+        global = true,
+        value_delimiter = ',',
+        default_value = DEFAULT_INCLUDE,
+        env = "REPORTS_INCLUDE"
+    )]
+    include: Vec<String>,
+
+    /// The glob patterns of the reports (or folders) to exclude (separated by commas)
+    ///
+    /// This takes precedence over `--include`.
+    #[arg(long, global = true, value_delimiter = ',', env = "REPORTS_EXCLUDE")]
+    exclude: Vec<String>,
+
+    /// The maximum number of nested folders to scan for reports
+    ///
+    /// Use `0` to only read the reports directly in the `--sketches-reports-source` folder.
+    #[arg(long, global = true, env = "REPORTS_MAX_DEPTH")]
+    max_depth: Option<usize>,
+
+    /// Follow symbolic links when scanning for reports
+    #[arg(
+        long,
+        global = true,
+        default_value_t = true,
+        action = ArgAction::Set,
+        env = "FOLLOW_SYMLINKS"
+    )]
+    follow_symlinks: bool,
+
+    /// The output of `arduino-cli compile --format json` before the changes (a file or folder)
+    ///
+    /// If specified (with `--arduino-cli-head`), then this is used instead of
+    /// the sketches' reports. Files in a folder are paired with the head build's files by name.
+    #[arg(long, requires = "arduino_cli_head", env = "ARDUINO_CLI_BASE")]
+    arduino_cli_base: Option<PathBuf>,
+
+    /// The output of `arduino-cli compile --format json` after the changes (a file or folder)
+    #[arg(long, requires = "arduino_cli_base", env = "ARDUINO_CLI_HEAD")]
+    arduino_cli_head: Option<PathBuf>,
+
+    /// The folder that the names of sketches compiled by arduino-cli are relative to
+    ///
+    /// By default, this is the `GITHUB_WORKSPACE` (or the current working directory).
+    #[arg(long, requires = "arduino_cli_head")]
+    sketches_root: Option<PathBuf>,
+
+    /// The output of PlatformIO builds (`pio run`) before the changes (a log file or folder)
+    ///
+    /// If specified (with `--platformio-head`), then this is used instead of
+    /// the sketches' reports. Each PlatformIO environment is reported as a board.
+    #[arg(long, requires = "platformio_head", env = "PLATFORMIO_BASE")]
+    platformio_base: Option<PathBuf>,
+
+    /// The output of PlatformIO builds (`pio run`) after the changes (a log file or folder)
+    #[arg(long, requires = "platformio_base", env = "PLATFORMIO_HEAD")]
+    platformio_head: Option<PathBuf>,
+
+    /// The compiled ELF files before the changes (a `.elf` file or folder)
+    ///
+    /// If specified (with `--elf-head`), then the sizes are computed from the ELF files' sections
+    /// instead of using the sketches' reports. The board is taken from the name of
+    /// the folder containing each ELF file (for example, `arduino.avr.uno`).
+    #[arg(long, requires = "elf_head", env = "ELF_BASE")]
+    elf_base: Option<PathBuf>,
+
+    /// The compiled ELF files after the changes (a `.elf` file or folder)
+    #[arg(long, requires = "elf_base", env = "ELF_HEAD")]
+    elf_head: Option<PathBuf>,
+
     /// The GitHub access token used to post comments on the PR thread
     #[arg(short, long, env = "GITHUB_TOKEN")]
     token: Option<String>,
+
+    /// Annotate sketches whose flash or RAM grew by more than this many bytes with a warning
+    ///
+    /// A check run is concluded as "neutral" if this is exceeded.
+    #[arg(long, env = "WARNING_THRESHOLD")]
+    warning_threshold: Option<i64>,
+
+    /// Annotate sketches whose flash or RAM grew by more than this many bytes with an error
+    ///
+    /// A check run or commit status is concluded as "failure" if this is exceeded.
+    #[arg(long, env = "ERROR_THRESHOLD")]
+    error_threshold: Option<i64>,
+
+    /// The kind(s) of feedback to give (separated by commas)
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "comment",
+        env = "FEEDBACK"
+    )]
+    feedback: Vec<Feedback>,
+
+    /// The git server that feedback is given to
+    ///
+    /// By default, this is detected from the CI environment.
+    #[arg(long, value_enum, default_value = "auto", env = "GIT_SERVER")]
+    git_server: GitServer,
+
+    /// Preview the comment locally instead of posting it
+    ///
+    /// No CI context is needed. The REST API calls that would have been made are logged,
+    /// and the comment is written to stdout (or the `--output` file).
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Write the comment to this file instead of posting it (implies `--dry-run`)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Also write a self-contained HTML report to this file
+    #[arg(long, env = "HTML_REPORT")]
+    html: Option<PathBuf>,
+
+    /// A minijinja template file used to render the comment
+    ///
+    /// If not specified, then the built-in markdown comment is used.
+    #[arg(long, env = "COMMENT_TEMPLATE")]
+    template: Option<PathBuf>,
+
+    /// The units used to format changes in memory size (in bytes)
+    #[arg(long, value_enum, default_value = "bytes", env = "UNITS")]
+    units: Units,
+
+    /// Group the digits of changes in memory size by thousands (e.g. 1,048,576)
+    #[arg(long, env = "THOUSANDS_SEPARATOR")]
+    thousands_separator: bool,
+
+    /// The maximum number of decimal places in percentages
+    #[arg(long, default_value_t = 3, env = "PRECISION")]
+    precision: usize,
+
+    /// The symbols used to emphasize increases and decreases
+    ///
+    /// By default, GitHub's emoji short-codes are used in the comment
+    /// and no symbols are used in the other formats.
+    #[arg(long, value_enum, env = "SYMBOLS")]
+    symbols: Option<Symbols>,
+
+    /// The SHA of the commit that the reports are expected to be compiled from
+    ///
+    /// By default, this is detected from the CI environment.
+    #[arg(long, env = "EXPECTED_COMMIT")]
+    expected_commit: Option<String>,
+
+    /// The URL of the repository that the reports were compiled from
+    ///
+    /// This is used to link sketches if a report's commit URL is unknown.
+    /// By default, this is derived from the `GITHUB_SERVER_URL` and `GITHUB_REPOSITORY`
+    /// environment variables.
+    #[arg(long, env = "REPOSITORY_URL")]
+    repository_url: Option<String>,
+
+    /// What to do if any reports were compiled from an unexpected commit
+    #[arg(long, value_enum, default_value = "warn", env = "COMMIT_MISMATCH")]
+    commit_mismatch: CommitMismatch,
+
+    /// The format of the report
+    ///
+    /// Formats other than markdown are printed to stdout (or written to the `--output` file).
+    /// No feedback is given for these formats.
+    #[arg(long, value_enum, default_value = "markdown")]
+    format: Format,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// The subcommands that do not post (or render) a report.
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Validate sketches' reports (JSON files) against the JSON schema shipped with this tool
+    ///
+    /// Each problem is printed with the path to the missing or wrongly typed field.
+    /// Fails if any report does not match the schema.
+    /// Folders are scanned with the `--include`, `--exclude`, `--max-depth` and
+    /// `--follow-symlinks` options.
+    Validate {
+        /// The reports (JSON files) or folders (scanned for JSON files) to validate
+        ///
+        /// Use `-` to read the reports from stdin.
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+}
+
+/// The formats that the report can be rendered in.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// A markdown comment (used for all kinds of feedback)
+    Markdown,
+
+    /// Aligned, colored tables for local use
+    Terminal,
+
+    /// Aligned tables without colors
+    Plain,
+
+    /// An HTML fragment
+    Html,
+
+    /// The aggregated data as JSON
+    Json,
+}
+
+impl Format {
+    /// Get the [`Renderer`] used to print the report to stdout.
+    ///
+    /// The terminal format is only colored if `color` is `true`.
+    /// Returns [`None`] for the markdown format, which is used to give feedback instead.
+    fn renderer(
+        &self,
+        sizes: SizeFormat,
+        symbols: SymbolSet,
+        color: bool,
+    ) -> Option<Box<dyn Renderer>> {
+        match self {
+            Format::Markdown => None,
+            Format::Terminal => Some(Box::new(TerminalRenderer {
+                sizes,
+                color,
+                symbols,
+            })),
+            Format::Plain => Some(Box::new(PlainTextRenderer { sizes, symbols })),
+            Format::Html => Some(Box::new(HtmlRenderer { sizes, symbols })),
+            Format::Json => Some(Box::new(JsonRenderer)),
+        }
+    }
+}
+
+/// The units used to format a number of bytes.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Units {
+    /// A plain number of bytes (e.g. 1048576)
+    Bytes,
+
+    /// The largest applicable binary unit (e.g. 1 MiB)
+    Binary,
+}
+
+impl From<Units> for ByteUnits {
+    fn from(units: Units) -> Self {
+        match units {
+            Units::Bytes => ByteUnits::Bytes,
+            Units::Binary => ByteUnits::Binary,
+        }
+    }
+}
+
+/// The sets of symbols that emphasize changes in memory usage.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Symbols {
+    /// GitHub's emoji short-codes (e.g. `:green_heart:`)
+    Github,
+
+    /// Unicode emoji (for markdown viewers other than GitHub)
+    Unicode,
+
+    /// Simple text symbols (▼, ▲, and ±)
+    Text,
+
+    /// No symbols
+    None,
+}
+
+impl From<Symbols> for SymbolSet {
+    fn from(symbols: Symbols) -> Self {
+        match symbols {
+            Symbols::Github => SymbolSet::GitHub,
+            Symbols::Unicode => SymbolSet::Unicode,
+            Symbols::Text => SymbolSet::Text,
+            Symbols::None => SymbolSet::None,
+        }
+    }
+}
+
+/// The ways to handle reports that were compiled from an unexpected commit.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum CommitMismatch {
+    /// Log a warning (the affected boards are also listed in the comment)
+    Warn,
+
+    /// Fail without giving any feedback
+    Fail,
+}
+
+/// The kinds of feedback given about the changes in memory usage.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Feedback {
+    /// Post a comment on the PR thread (or append to the step summary for non-PR events)
+    Comment,
+
+    /// Create a GitHub check run with the report as its summary and regressions as annotations
+    CheckRun,
+
+    /// Create a GitHub commit status per board with a short summary of the changes
+    CommitStatus,
+}
+
+impl Args {
+    /// Is the comment only previewed locally?
+    fn is_dry_run(&self) -> bool {
+        self.dry_run || self.output.is_some()
+    }
+
+    /// Load the reports from the sketches' reports, the output of arduino-cli or PlatformIO,
+    /// or compiled ELF files.
+    ///
+    /// The sketches' reports are read from the given `input` if the source is `-`.
+    fn load_reports<R: Read>(&self, input: R) -> Result<Vec<Report>, CommentAssemblyError> {
+        let (tool, base, head, mut report) = if let (Some(base), Some(head)) =
+            (&self.arduino_cli_base, &self.arduino_cli_head)
+        {
+            let root = self.sketches_root();
+            (
+                "arduino-cli",
+                base,
+                head,
+                parse_arduino_cli(base, head, &root)?,
+            )
+        } else if let (Some(base), Some(head)) = (&self.platformio_base, &self.platformio_head) {
+            ("PlatformIO", base, head, parse_platformio(base, head)?)
+        } else if let (Some(base), Some(head)) = (&self.elf_base, &self.elf_head) {
+            ("ELF files", base, head, parse_elf(base, head)?)
+        } else if self.sketches_reports_source.as_os_str() == STDIN_SOURCE {
+            return load_reports_from_reader(input);
+        } else {
+            return load_reports_with(&self.sketches_reports_source, &self.discovery_options());
+        };
+        if report.boards.is_empty() {
+            log::error!(
+                "No builds of {tool} found to compare (in paths {} and {}).",
+                base.to_string_lossy(),
+                head.to_string_lossy()
+            );
+            return Err(CommentAssemblyError::NotFound);
+        }
+        // build tools do not know which commit was compiled
+        report.commit_hash = self.expected_commit().unwrap_or_default();
+        Ok(vec![report])
+    }
+
+    /// Get the folder that the names of sketches compiled by arduino-cli are relative to.
+    fn sketches_root(&self) -> PathBuf {
+        self.sketches_root
+            .clone()
+            .or_else(|| env::var_os("GITHUB_WORKSPACE").map(PathBuf::from))
+            .or_else(|| env::current_dir().ok())
+            .unwrap_or_default()
+    }
+
+    /// Get the options used to discover the sketches' reports.
+    fn discovery_options(&self) -> DiscoveryOptions {
+        DiscoveryOptions {
+            include: self.include.clone(),
+            exclude: self.exclude.clone(),
+            max_depth: self.max_depth,
+            follow_symlinks: self.follow_symlinks,
+        }
+    }
+
+    /// Get the SHA of the commit that the reports are expected to be compiled from.
+    ///
+    /// If not specified, then this is detected from the CI environment (if possible).
+    fn expected_commit(&self) -> Option<String> {
+        self.expected_commit
+            .clone()
+            .or_else(|| GithubContext::head_sha().ok())
+            .or_else(|| env::var("CI_COMMIT_SHA").ok())
+            .or_else(|| env::var("BITBUCKET_COMMIT").ok())
+            .filter(|sha| !sha.is_empty())
+    }
+
+    /// Get the URL of the repository that the reports were compiled from.
+    ///
+    /// If not specified, then this is derived from the CI environment (if possible).
+    fn repository_url(&self) -> Option<String> {
+        if let Some(url) = self.repository_url.as_ref().filter(|url| !url.is_empty()) {
+            return Some(url.clone());
+        }
+        match (env::var("GITHUB_SERVER_URL"), env::var("GITHUB_REPOSITORY")) {
+            (Ok(server), Ok(repo)) if !server.is_empty() && !repo.is_empty() => {
+                Some(format!("{}/{repo}", server.trim_end_matches('/')))
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the configured options used to format changes in memory size.
+    fn size_format(&self) -> SizeFormat {
+        SizeFormat {
+            units: self.units.into(),
+            thousands_separator: self.thousands_separator,
+            precision: self.precision,
+        }
+    }
+
+    /// Get the configured symbols used to emphasize changes in the given `format`.
+    ///
+    /// If not specified, then GitHub's emoji short-codes are used for the markdown format
+    /// and no symbols are used for the other formats.
+    fn symbols(&self, format: Format) -> SymbolSet {
+        match (self.symbols, format) {
+            (Some(symbols), _) => symbols.into(),
+            (None, Format::Markdown) => SymbolSet::GitHub,
+            (None, _) => SymbolSet::None,
+        }
+    }
+
+    /// Get the configured thresholds used to detect regressions in compile size.
+    fn thresholds(&self) -> Thresholds {
+        Thresholds {
+            warning: self.warning_threshold,
+            error: self.error_threshold,
+        }
+    }
+}
+
+/// Convert a detected [`Regression`] into a [`FileAnnotation`] about the sketch's source file.
+fn regression_annotation(regression: &Regression) -> FileAnnotation {
+    FileAnnotation {
+        severity: match regression.severity {
+            Severity::Warning => AnnotationLevel::Warning,
+            Severity::Error => AnnotationLevel::Error,
+        },
+        path: regression.file.clone(),
+        title: Some(format!("Increased {} usage", regression.memory)),
+        message: format!(
+            "Compiling {} for {} uses {:+} bytes more {}",
+            regression.sketch, regression.board, regression.delta, regression.memory
+        ),
+        ..Default::default()
+    }
 }
 
 struct Logger;
@@ -50,40 +510,45 @@ impl log::Log for Logger {
         metadata.level() <= log::max_level()
     }
 
+    /// Write the log `record` to stderr.
+    ///
+    /// Only the commands that manipulate a CI workflow's log grouping are written to stdout,
+    /// so that stdout only contains the report (for example, when previewing the comment).
     fn log(&self, record: &Record) {
-        let mut stdout = stdout().lock();
         if record.target() == "CI_LOG_GROUPING" {
             // this log is meant to manipulate a CI workflow's log grouping
+            let mut stdout = stdout().lock();
             writeln!(stdout, "{}", record.args()).expect("Failed to write log command to stdout");
             stdout
                 .flush()
                 .expect("Failed to flush log command to stdout");
         } else if self.enabled(record.metadata()) {
+            let mut stderr = stderr().lock();
             let module = record.module_path();
             if module.is_none_or(|v| {
                 v.starts_with("arduino_report_size_deltas") || v.starts_with("report_size_deltas")
             }) {
                 writeln!(
-                    stdout,
+                    stderr,
                     "[{}]: {}",
                     Self::level_color(&record.level()),
                     record.args()
                 )
-                .expect("Failed to write log message to stdout");
+                .expect("Failed to write log message to stderr");
             } else {
                 writeln!(
-                    stdout,
+                    stderr,
                     "[{}]{{{}:{}}}: {}",
                     Self::level_color(&record.level()),
                     module.unwrap(), // safe to unwrap here because the None case is caught above
                     record.line().unwrap_or_default(),
                     record.args()
                 )
-                .expect("Failed to write detailed log message to stdout");
+                .expect("Failed to write detailed log message to stderr");
             }
-            stdout
+            stderr
                 .flush()
-                .expect("Failed to flush log message to stdout");
+                .expect("Failed to flush log message to stderr");
         }
     }
 
@@ -96,10 +561,106 @@ pub fn logger_init() {
     let _ = log::set_logger(&LOGGER);
 }
 
+/// Check that all reports were compiled from the expected commit.
+///
+/// Any mismatched commits are logged as warnings.
+/// Returns an error if any commits mismatched and the [`CommitMismatch::Fail`] policy is used.
+fn check_commits(summary: &Summary, args: &Args) -> Result<()> {
+    let expected = args.expected_commit();
+    let mismatched = summary.mismatched_commits(expected.as_deref());
+    let expected = expected.as_deref().unwrap_or(&summary.commit_hash);
+    for commit in &mismatched {
+        log::warn!(
+            "Reports for {} were compiled from commit {} (expected {expected})",
+            commit.boards.join(", "),
+            commit.commit_hash
+        );
+    }
+    if !mismatched.is_empty() && args.commit_mismatch == CommitMismatch::Fail {
+        return Err(anyhow!(
+            "Reports of {} board(s) were not compiled from the expected commit {expected}",
+            mismatched.iter().map(|c| c.boards.len()).sum::<usize>()
+        ));
+    }
+    Ok(())
+}
+
+/// Validate the reports at the given `paths` (or discovered with the given `options`)
+/// and print any problems found.
+///
+/// The reports are read from the given `input` if a path is `-`.
+fn validate<R: Read>(paths: &[PathBuf], options: &DiscoveryOptions, mut input: R) -> Result<()> {
+    let mut out = stdout().lock();
+    let mut invalid = 0;
+    for path in paths {
+        let results = if path.as_os_str() == STDIN_SOURCE {
+            validate_reader(&mut input)
+                .map_err(|e| anyhow!("Failed to validate reports from stdin: {e}"))?
+                .into_iter()
+                .enumerate()
+                .map(|(i, issues)| (PathBuf::from(format!("<stdin>[{i}]")), issues))
+                .collect()
+        } else {
+            validate_artifacts(path, options)
+                .map_err(|e| anyhow!("Failed to validate {}: {e}", path.display()))?
+        };
+        for (file, issues) in results {
+            let file = file.display();
+            if issues.is_empty() {
+                writeln!(out, "{file}: valid")?;
+                continue;
+            }
+            invalid += 1;
+            writeln!(out, "{file}: {} problem(s) found", issues.len())?;
+            for issue in issues {
+                writeln!(out, "  {issue}")?;
+            }
+        }
+    }
+    if invalid > 0 {
+        return Err(anyhow!(
+            "{invalid} report(s) do not match the JSON schema (version {SCHEMA_VERSION})"
+        ));
+    }
+    Ok(())
+}
+
 async fn run(args: &[String]) -> Result<()> {
+    run_with_input(args, stdin()).await
+}
+
+/// Run with the given `args`, reading any reports from the given `input` (instead of stdin).
+async fn run_with_input<R: Read>(args: &[String], input: R) -> Result<()> {
     let args = Args::parse_from(args);
     logger_init();
-    let client = init_client()?;
+    if let Some(Command::Validate { paths }) = &args.command {
+        return validate(paths, &args.discovery_options(), input);
+    }
+    let repository = args.repository_url();
+    // only color the report printed to a terminal (unless disabled via `NO_COLOR`)
+    let color = args.output.is_none() && colored::control::SHOULD_COLORIZE.should_colorize();
+    if let Some(renderer) =
+        args.format
+            .renderer(args.size_format(), args.symbols(args.format), color)
+    {
+        let reports = args
+            .load_reports(input)
+            .map_err(|e| anyhow!("Failed to load reports: {e}"))?;
+        let report = renderer.render(&Summary::for_repository(&reports, repository.as_deref()));
+        match &args.output {
+            Some(output) => fs::write(output, report + "\n")
+                .with_context(|| format!("Failed to write report to {}", output.display()))?,
+            None => writeln!(stdout().lock(), "{report}")?,
+        }
+        return Ok(());
+    }
+    let client: Box<dyn RestApiClient + Send + Sync> = if args.is_dry_run() {
+        Box::new(DryRunClient {
+            output: args.output.clone(),
+        })
+    } else {
+        init_client(args.git_server, args.token.clone())?
+    };
     log::set_max_level(if client.is_debug_enabled() {
         LevelFilter::Debug
     } else {
@@ -107,31 +668,133 @@ async fn run(args: &[String]) -> Result<()> {
     });
 
     client.start_log_group("Generating comment from JSON files");
-    let comment = generate_comment(&args.sketches_reports_source);
+    let reports = args.load_reports(input);
     client.end_log_group("Generating comment from JSON files");
+    let reports = reports.map_err(|e| anyhow!("Failed to assemble comment:, {e}"))?;
+    let summary = Summary::for_repository(&reports, repository.as_deref())
+        .with_expected_commit(args.expected_commit().as_deref());
+    check_commits(&summary, &args)?;
+    let comment = match &args.template {
+        Some(template) => TemplateRenderer::from_file(template)
+            .and_then(|renderer| {
+                renderer
+                    .with_symbols(args.symbols(Format::Markdown))
+                    .with_sizes(args.size_format())
+                    .try_render(&summary)
+            })
+            .with_context(|| format!("Failed to render template {}", template.display()))?,
+        None => MarkdownRenderer {
+            symbols: args.symbols(Format::Markdown),
+            sizes: args.size_format(),
+        }
+        .render(&summary),
+    };
 
-    match comment {
-        Ok(comment) => {
-            if !client.is_pr_event() {
-                log::info!("Appending to step summary");
-                client.append_step_summary(&comment)?;
-                Ok(())
-            } else {
-                log::info!("Posting comment");
-                client
-                    .post_thread_comment(ThreadCommentOptions {
-                        comment,
-                        marker: COMMENT_MARKER.to_string(),
-                        policy: CommentPolicy::Update,
-                        ..Default::default()
-                    })
-                    .await
-                    .with_context(|| "Failed to post comment")?;
-                Ok(())
+    if let Some(html) = &args.html {
+        fs::write(
+            html,
+            HtmlReportRenderer {
+                sizes: args.size_format(),
             }
+            .render(&summary),
+        )
+        .with_context(|| format!("Failed to write HTML report to {}", html.display()))?;
+        log::info!("Wrote HTML report to {}", html.display());
+    }
+
+    let regressions = find_regressions(&reports, &args.thresholds());
+    let annotations = regressions
+        .iter()
+        .map(regression_annotation)
+        .collect::<Vec<_>>();
+
+    let mut annotated = false;
+    if args.feedback.contains(&Feedback::CheckRun) {
+        let title = match regressions.len() {
+            0 => "No size regressions".to_string(),
+            count => format!("{count} size regression(s)"),
+        };
+        let conclusion = check_run_conclusion(regressions.iter().map(|r| r.severity).max());
+        match client.client_kind().as_str() {
+            "github" => {
+                log::info!("Creating check run");
+                GithubContext::new(args.token.as_deref())?
+                    .create_check_run(
+                        &title,
+                        comment.trim_start_matches(COMMENT_MARKER),
+                        conclusion,
+                        &annotations,
+                    )
+                    .await?;
+                annotated = true;
+            }
+            "dry-run" => {
+                log::info!(
+                    "Would create check run \"{title}\" (concluded as {conclusion}) with {} annotation(s)",
+                    annotations.len()
+                );
+            }
+            _ => log::warn!("Check runs are only supported on GitHub"),
+        }
+    }
+
+    if args.feedback.contains(&Feedback::CommitStatus) {
+        let statuses = board_statuses(&reports, &args.thresholds())
+            .into_iter()
+            .map(|status| {
+                let mut description = status.description;
+                if status.severity == Some(Severity::Warning) {
+                    description.push_str(" (exceeds warning threshold)");
+                }
+                (
+                    status.board,
+                    commit_status_state(status.severity),
+                    description,
+                )
+            });
+        match client.client_kind().as_str() {
+            "github" => {
+                log::info!("Creating commit statuses");
+                let github = GithubContext::new(args.token.as_deref())?;
+                for (board, state, description) in statuses {
+                    github
+                        .create_commit_status(&board, state, &description)
+                        .await?;
+                }
+            }
+            "dry-run" => {
+                for (board, state, description) in statuses {
+                    log::info!("Would create commit status for {board} ({state}): {description}");
+                }
+            }
+            _ => log::warn!("Commit statuses are only supported on GitHub"),
         }
-        Err(e) => Err(anyhow!("Failed to assemble comment:, {e}")),
     }
+
+    if !annotated && !annotations.is_empty() {
+        log::info!("Annotating {} size regression(s)", annotations.len());
+        client.write_file_annotations(&annotations)?;
+    }
+
+    if !args.feedback.contains(&Feedback::Comment) {
+        return Ok(());
+    }
+    if !client.is_pr_event() {
+        log::info!("Appending to step summary");
+        client.append_step_summary(&comment)?;
+    } else {
+        log::info!("Posting comment");
+        client
+            .post_thread_comment(ThreadCommentOptions {
+                comment,
+                marker: COMMENT_MARKER.to_string(),
+                policy: CommentPolicy::Update,
+                ..Default::default()
+            })
+            .await
+            .with_context(|| "Failed to post comment")?;
+    }
+    Ok(())
 }
 
 #[tokio::main]
@@ -141,27 +804,48 @@ async fn main() -> Result<()> {
 
 #[cfg(test)]
 mod test {
-    use arduino_report_size_deltas::{COMMENT_MARKER, CommentAssemblyError};
+    use anyhow::Result;
+    use arduino_report_size_deltas::{
+        COMMENT_MARKER, CommentAssemblyError, MemoryKind, Regression, Severity,
+    };
+    use git_bot_feedback::AnnotationLevel;
     use mockito::{Matcher, Server};
     use std::{
         env, fs,
-        io::{Read, Write},
+        io::{self, Read, Write},
     };
     use tempfile::NamedTempFile;
+    use tokio::sync::Mutex;
 
-    use crate::run;
+    use crate::{
+        backends::github::{CHECK_RUN_NAME, STATUS_CONTEXT_PREFIX},
+        regression_annotation, run, run_with_input,
+    };
 
     const REPO: &str = "2bndy5/arduino-report-size-deltas";
     const PR: u64 = 22;
     const TOKEN: &str = "123456";
+    const SHA: &str = "deadbeef";
+    const PR_HEAD_SHA: &str = "c0ffee";
+
+    /// A lock to prevent tests from concurrently altering environment variables.
+    static ENV_LOCK: Mutex<()> = Mutex::const_new(());
 
     #[derive(Debug, Default)]
     struct TestParams {
         no_report_data: bool,
         is_not_pr: bool,
+        with_thresholds: bool,
+        /// The value of the `FEEDBACK` environment variable (if any).
+        feedback: Option<&'static str>,
+        /// Post the comment via Gitea's REST API instead of GitHub's.
+        gitea: bool,
+        /// Add a second report for one of the boards.
+        duplicate_reports: bool,
     }
 
     async fn setup_test(test_params: TestParams) {
+        let _env_lock = ENV_LOCK.lock().await;
         let mut server = Server::new_async().await;
         let mut event_payload_path = NamedTempFile::new().unwrap();
         let mut gh_summary_path = NamedTempFile::new().unwrap();
@@ -173,17 +857,54 @@ mod test {
                     "state": "open",
                     "number": PR,
                     "locked": false,
+                    "head": { "sha": PR_HEAD_SHA },
                 }})
                 .to_string()
                 .as_bytes(),
             )
             .unwrap();
+        // a folder with a single report that has no applicable data
+        let no_data = test_params.no_report_data.then(|| {
+            let dir = tempfile::tempdir().unwrap();
+            fs::copy(
+                "tests/size-deltas-reports-old/arduino-mbed_portenta-envie_m7.json",
+                dir.path().join("report.json"),
+            )
+            .unwrap();
+            dir
+        });
+        // the new reports plus a copy of one of them (with a different file name)
+        let duplicates = test_params.duplicate_reports.then(|| {
+            let dir = tempfile::tempdir().unwrap();
+            for entry in fs::read_dir("tests/size-deltas-reports-new").unwrap() {
+                let path = entry.unwrap().path();
+                fs::copy(&path, dir.path().join(path.file_name().unwrap())).unwrap();
+            }
+            fs::copy(
+                "tests/size-deltas-reports-new/arduino-avr-uno.json",
+                dir.path().join("arduino-avr-uno-copy.json"),
+            )
+            .unwrap();
+            dir
+        });
+        let reports_source = if let Some(dir) = no_data.as_ref().or(duplicates.as_ref()) {
+            dir.path().to_str().unwrap()
+        } else if test_params.with_thresholds {
+            "tests/test_assets"
+        } else {
+            "tests/size-deltas-reports-new"
+        };
+        let feedback = test_params.feedback.unwrap_or("comment");
 
         unsafe {
             env::set_var("GITHUB_ACTIONS", "true");
             env::set_var("GITHUB_API_URL", server.url());
             env::set_var("GITHUB_REPOSITORY", REPO);
-            env::set_var("GITHUB_SHA", "deadbeef");
+            // the expected comments do not link sketches to the repository
+            env::remove_var("GITHUB_SERVER_URL");
+            env::remove_var("REPOSITORY_URL");
+            env::remove_var("EXPECTED_COMMIT");
+            env::set_var("GITHUB_SHA", SHA);
             env::set_var("GITHUB_TOKEN", TOKEN);
             if test_params.is_not_pr {
                 env::set_var("GITHUB_EVENT_NAME", "push");
@@ -192,17 +913,18 @@ mod test {
                 env::set_var("GITHUB_EVENT_NAME", "pull_request");
                 env::set_var("GITHUB_EVENT_PATH", event_payload_path.path());
             }
+            env::set_var("SKETCHES_REPORTS_SOURCE", reports_source);
+            if test_params.with_thresholds {
+                env::set_var("WARNING_THRESHOLD", "0");
+                env::set_var("ERROR_THRESHOLD", "64");
+            } else {
+                env::remove_var("WARNING_THRESHOLD");
+                env::remove_var("ERROR_THRESHOLD");
+            }
+            env::set_var("FEEDBACK", feedback);
             env::set_var(
-                "SKETCHES_REPORTS_SOURCE",
-                format!(
-                    "tests/size-deltas-reports-{}",
-                    if test_params.no_report_data {
-                        "old"
-                    } else {
-                        "new"
-                    }
-                )
-                .as_str(),
+                "GIT_SERVER",
+                if test_params.gitea { "gitea" } else { "github" },
             );
             if !test_params.no_report_data && env::var("ACTIONS_STEP_DEBUG").is_err() {
                 env::set_var("ACTIONS_STEP_DEBUG", "true");
@@ -216,56 +938,159 @@ mod test {
                     .contains(&CommentAssemblyError::NotFound.to_string())
             }));
             return;
-        } else if test_params.is_not_pr {
-            // Nothing is done with REST API.
-            // Should just append to step summary and exit 0.
-            assert!(run(&[]).await.is_ok());
-            let mut summary = String::new();
-            gh_summary_path.read_to_string(&mut summary).unwrap();
-            assert!(summary.contains(COMMENT_MARKER));
-            return;
         }
 
+        let sha = if test_params.is_not_pr {
+            SHA
+        } else {
+            PR_HEAD_SHA
+        };
         let mut mocks = vec![];
-        mocks.push(
-            server
-                .mock(
-                    "GET",
-                    format!("/repos/{REPO}/issues/{PR}/comments").as_str(),
-                )
-                .match_query(Matcher::Any)
-                .match_header("Accept", "application/vnd.github.raw+json")
-                .match_header("Authorization", format!("token {TOKEN}").as_str())
-                .match_body(Matcher::Any)
-                .with_body("[]")
-                .create(),
-        );
+        if feedback.contains("commit-status") {
+            let endpoint = format!("/repos/{REPO}/statuses/{sha}");
+            if test_params.with_thresholds {
+                mocks.push(
+                    server
+                        .mock("POST", endpoint.as_str())
+                        .match_body(Matcher::PartialJson(serde_json::json!({
+                            "state": "failure",
+                            "context": format!("{STATUS_CONTEXT_PREFIX}arduino:samd:mkrzero"),
+                            "description": "flash -28 to +84 B, RAM 0 to +32 B",
+                        })))
+                        .with_body("{}")
+                        .create(),
+                );
+            }
+            mocks.push(
+                server
+                    .mock("POST", endpoint.as_str())
+                    .match_header("Accept", "application/vnd.github+json")
+                    .match_header("Authorization", format!("token {TOKEN}").as_str())
+                    .match_body(Matcher::PartialJson(
+                        serde_json::json!({ "state": "success" }),
+                    ))
+                    .with_body("{}")
+                    .expect(if test_params.with_thresholds { 3 } else { 4 })
+                    .create(),
+            );
+        }
+        if feedback.contains("check-run") {
+            mocks.push(
+                server
+                    .mock("POST", format!("/repos/{REPO}/check-runs").as_str())
+                    .match_header("Accept", "application/vnd.github+json")
+                    .match_header("Authorization", format!("token {TOKEN}").as_str())
+                    .match_body(Matcher::PartialJson(serde_json::json!({
+                        "name": CHECK_RUN_NAME,
+                        "head_sha": sha,
+                        "status": "completed",
+                        "conclusion": if test_params.with_thresholds { "failure" } else { "success" },
+                    })))
+                    .with_body(r#"{"id":42}"#)
+                    .create(),
+            );
+        }
 
-        let expected_comment = fs::read_to_string("tests/size-deltas-reports-new/out.md").unwrap();
-        let expect_payload = format!(r#"{{"body":"{}"}}"#, expected_comment.escape_debug());
-        mocks.push(
-            server
-                .mock(
-                    "POST",
-                    format!("/repos/{REPO}/issues/{PR}/comments").as_str(),
-                )
-                .match_body(Matcher::Exact(expect_payload))
-                .match_header("Accept", "application/vnd.github.raw+json")
-                .match_header("Authorization", format!("token {TOKEN}").as_str())
-                .with_body("{}")
-                .match_header(
-                    "user-agent",
-                    Matcher::Regex(r"^git_bot_feedback/\d+\.\d+\.\d+".to_string()),
-                )
-                .create(),
-        );
+        // Nothing is done with REST API for comments on non-PR events.
+        // Should just append to step summary.
+        if feedback.contains("comment") && !test_params.is_not_pr {
+            let accept = if test_params.gitea {
+                "application/json"
+            } else {
+                "application/vnd.github.raw+json"
+            };
+            mocks.push(
+                server
+                    .mock(
+                        "GET",
+                        format!("/repos/{REPO}/issues/{PR}/comments").as_str(),
+                    )
+                    .match_query(Matcher::Any)
+                    .match_header("Accept", accept)
+                    .match_header("Authorization", format!("token {TOKEN}").as_str())
+                    .match_body(Matcher::Any)
+                    .with_body("[]")
+                    .create(),
+            );
+
+            // none of the reports were compiled from the commit that triggered the event
+            let expected_comment = fs::read_to_string(format!("{reports_source}/out.md"))
+                .unwrap()
+                .replace(
+                    "The reports were compiled from different commits:",
+                    &format!("The reports were not compiled from the expected commit `{sha}`:"),
+                );
+            let expect_payload = format!(r#"{{"body":"{}"}}"#, expected_comment.escape_debug());
+            mocks.push(
+                server
+                    .mock(
+                        "POST",
+                        format!("/repos/{REPO}/issues/{PR}/comments").as_str(),
+                    )
+                    .match_body(Matcher::Exact(expect_payload))
+                    .match_header("Accept", accept)
+                    .match_header("Authorization", format!("token {TOKEN}").as_str())
+                    .with_body("{}")
+                    .match_header(
+                        "user-agent",
+                        Matcher::Regex(r"^git_bot_feedback/\d+\.\d+\.\d+".to_string()),
+                    )
+                    .create(),
+            );
+        }
 
         run(&[]).await.unwrap();
+        let mut summary = String::new();
+        gh_summary_path.read_to_string(&mut summary).unwrap();
+        assert_eq!(
+            summary.contains(COMMENT_MARKER),
+            feedback.contains("comment") && test_params.is_not_pr
+        );
         for mock in mocks {
             mock.assert();
         }
     }
 
+    /// Preview the comment (in a dry run) with the given `args` and return the written comment.
+    ///
+    /// Unless overridden by the `args`, only a comment is given as feedback
+    /// and no thresholds are used. No CI context is needed for a dry run.
+    async fn run_to_file(args: &[&str]) -> Result<String> {
+        run_to_file_with_input(args, io::empty()).await
+    }
+
+    /// Like [`run_to_file()`], but any reports are read from the given `input`.
+    async fn run_to_file_with_input<R: Read>(args: &[&str], input: R) -> Result<String> {
+        let _env_lock = ENV_LOCK.lock().await;
+        unsafe {
+            for name in [
+                "GITHUB_ACTIONS",
+                "GITHUB_SERVER_URL",
+                "REPOSITORY_URL",
+                "EXPECTED_COMMIT",
+                "GITHUB_SHA",
+                "GITHUB_EVENT_NAME",
+                "CI_COMMIT_SHA",
+                "BITBUCKET_COMMIT",
+                "WARNING_THRESHOLD",
+                "ERROR_THRESHOLD",
+            ] {
+                env::remove_var(name);
+            }
+            env::set_var("FEEDBACK", "comment");
+        }
+        let output = NamedTempFile::new().unwrap();
+        let mut all_args = vec![
+            "report-size-deltas",
+            "--output",
+            output.path().to_str().unwrap(),
+        ];
+        all_args.extend_from_slice(args);
+        let all_args = all_args.into_iter().map(String::from).collect::<Vec<_>>();
+        run_with_input(&all_args, input).await?;
+        Ok(fs::read_to_string(output.path()).unwrap())
+    }
+
     #[tokio::test]
     async fn normal() {
         setup_test(TestParams::default()).await;
@@ -294,4 +1119,393 @@ mod test {
         })
         .await;
     }
+
+    #[tokio::test]
+    async fn annotate_regressions() {
+        setup_test(TestParams {
+            is_not_pr: true,
+            with_thresholds: true,
+            ..Default::default()
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn check_run() {
+        setup_test(TestParams {
+            feedback: Some("check-run"),
+            ..Default::default()
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn check_run_with_regressions() {
+        setup_test(TestParams {
+            is_not_pr: true,
+            with_thresholds: true,
+            feedback: Some("comment,check-run"),
+            ..Default::default()
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn commit_status() {
+        setup_test(TestParams {
+            feedback: Some("commit-status"),
+            ..Default::default()
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn commit_status_per_board() {
+        setup_test(TestParams {
+            duplicate_reports: true,
+            feedback: Some("commit-status"),
+            ..Default::default()
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn commit_status_with_regressions() {
+        setup_test(TestParams {
+            is_not_pr: true,
+            with_thresholds: true,
+            feedback: Some("commit-status"),
+            ..Default::default()
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn gitea() {
+        setup_test(TestParams {
+            gitea: true,
+            ..Default::default()
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn dry_run() {
+        let html = NamedTempFile::new().unwrap();
+        let comment = run_to_file(&[
+            "--sketches-reports-source",
+            "tests/test_assets",
+            "--feedback",
+            "comment,check-run,commit-status",
+            "--warning-threshold",
+            "0",
+            "--html",
+            html.path().to_str().unwrap(),
+        ])
+        .await
+        .unwrap();
+        let expected = fs::read_to_string("tests/test_assets/out.md").unwrap();
+        assert_eq!(comment, expected);
+        assert!(
+            fs::read_to_string(html.path())
+                .unwrap()
+                .starts_with("<!DOCTYPE html>")
+        );
+    }
+
+    #[tokio::test]
+    async fn commit_mismatch() {
+        let args = |policy| {
+            [
+                "--sketches-reports-source",
+                "tests/test_assets",
+                "--expected-commit",
+                "651f05f4d4aca30ac359e972c01568f873112d43",
+                "--commit-mismatch",
+                policy,
+            ]
+        };
+        let comment = run_to_file(&args("warn")).await.unwrap();
+        assert!(
+            comment.contains("The reports were not compiled from the expected commit `651f05f`")
+        );
+        let err = run_to_file(&args("fail")).await.unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("Reports of 1 board(s) were not compiled")
+        );
+    }
+
+    #[tokio::test]
+    async fn stale_commit() {
+        // all reports agree on a commit that is not the expected commit
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["arduino-avr-leonardo.json", "arduino-avr-uno.json"] {
+            fs::copy(
+                format!("tests/size-deltas-reports-new/{name}"),
+                dir.path().join(name),
+            )
+            .unwrap();
+        }
+        let comment = run_to_file(&[
+            "--sketches-reports-source",
+            dir.path().to_str().unwrap(),
+            "--expected-commit",
+            "c0ffee",
+        ])
+        .await
+        .unwrap();
+        assert!(comment.contains(concat!(
+            "> The reports were not compiled from the expected commit `c0ffee`:\n>\n",
+            "> - [`d8fd302`](https://example.com/foo): `arduino:avr:leonardo`, `arduino:avr:uno`\n"
+        )));
+    }
+
+    #[tokio::test]
+    async fn custom_template() {
+        let mut template = NamedTempFile::new().unwrap();
+        template
+            .write_all(b"{% for board in boards %}{{ board.board }}: {{ board.flash | headline }}\n{% endfor %}")
+            .unwrap();
+        let comment = run_to_file(&[
+            "--sketches-reports-source",
+            "tests/test_assets",
+            "--template",
+            template.path().to_str().unwrap(),
+        ])
+        .await
+        .unwrap();
+        assert!(comment.starts_with(COMMENT_MARKER));
+        assert!(comment.contains("arduino:samd:mkrzero: "));
+    }
+
+    #[tokio::test]
+    async fn size_format() {
+        let comment = run_to_file(&[
+            "--sketches-reports-source",
+            "tests/test_assets",
+            "--units",
+            "binary",
+            "--precision",
+            "1",
+            "--symbols",
+            "none",
+        ])
+        .await
+        .unwrap();
+        assert!(comment.contains("| arduino:avr:leonardo | -40 B - 0 B | -0.1 - 0 |"));
+    }
+
+    #[tokio::test]
+    async fn arduino_cli_builds() {
+        let comment = run_to_file(&[
+            "--arduino-cli-base",
+            "tests/arduino-cli/base",
+            "--arduino-cli-head",
+            "tests/arduino-cli/head",
+            "--expected-commit",
+            "deadbeef",
+            "--sketches-root",
+            "/home/runner/work/lib/lib",
+        ])
+        .await
+        .unwrap();
+        assert!(comment.contains("### Memory usage change @ "));
+        assert!(comment.contains("| arduino:avr:uno | :grey_question: -40 - +32 |"));
+        assert!(!comment.contains("different commits"));
+        assert!(comment.contains("| examples/Blink |"));
+    }
+
+    #[tokio::test]
+    async fn platformio_builds() {
+        let comment = run_to_file(&[
+            "--platformio-base",
+            "tests/platformio/base",
+            "--platformio-head",
+            "tests/platformio/head",
+            "--expected-commit",
+            "deadbeef",
+        ])
+        .await
+        .unwrap();
+        assert!(comment.contains("| esp32dev | :small_red_triangle: +128 - +128 |"));
+    }
+
+    #[tokio::test]
+    async fn elf_builds() {
+        let comment = run_to_file(&[
+            "--elf-base",
+            "tests/elf/base",
+            "--elf-head",
+            "tests/elf/head",
+            "--expected-commit",
+            "deadbeef",
+        ])
+        .await
+        .unwrap();
+        assert!(comment.contains("| esp32:esp32:esp32 | :small_red_triangle: +128 - +128 |"));
+    }
+
+    #[tokio::test]
+    async fn stdout_formats() {
+        let render = async |format| {
+            run_to_file(&[
+                "--sketches-reports-source",
+                "tests/test_assets",
+                "--format",
+                format,
+            ])
+            .await
+            .unwrap()
+        };
+        let leonardo = "arduino:avr:leonardo       -40 - 0    -0.14 - 0      -16 - 0";
+        for format in ["terminal", "plain"] {
+            let out = render(format).await;
+            assert!(out.starts_with("Memory usage change @ 651f05f"), "{format}");
+            assert!(out.contains(leonardo), "{format}");
+            assert!(!out.contains(COMMENT_MARKER), "{format}");
+        }
+
+        let out = render("html").await;
+        assert!(out.starts_with("<h3>Memory usage change @ <code>651f05f"));
+        assert!(out.contains(r#"<td class="decrease" data-sort="0">-40 - 0</td>"#));
+
+        // the symbols are only used if specified (except for the comment)
+        for format in ["terminal", "plain", "html"] {
+            let out = run_to_file(&[
+                "--sketches-reports-source",
+                "tests/test_assets",
+                "--format",
+                format,
+                "--symbols",
+                "text",
+            ])
+            .await
+            .unwrap();
+            assert!(out.contains("\u{25bc} -40 - 0"), "{format}");
+            assert!(out.contains("\u{25b2} +84"), "{format}");
+        }
+
+        let out = serde_json::from_str::<serde_json::Value>(&render("json").await).unwrap();
+        assert_eq!(
+            out["commit_hash"],
+            "651f05f4d4aca30ac359e972c01568f873112d43"
+        );
+        assert_eq!(out["boards"].as_array().unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn validate_reports() {
+        let _env_lock = ENV_LOCK.lock().await;
+        let args = ["report-size-deltas", "validate", "tests/test_assets"].map(String::from);
+        run(&args).await.unwrap();
+
+        let mut invalid = NamedTempFile::with_suffix(".json").unwrap();
+        invalid
+            .write_all(br#"{"commit_hash": "deadbeef", "boards": [{"board": 42}]}"#)
+            .unwrap();
+        let args = [
+            "report-size-deltas",
+            "validate",
+            "tests/size-deltas-reports-new",
+            invalid.path().to_str().unwrap(),
+        ]
+        .map(String::from);
+        let err = run(&args).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1 report(s) do not match the JSON schema (version 1)"
+        );
+        // the discovery options also apply to the scanned folders
+        let dir = tempfile::tempdir().unwrap();
+        fs::copy(invalid.path(), dir.path().join("invalid.json")).unwrap();
+        let dir = dir.path().to_str().unwrap();
+        let args = [
+            "report-size-deltas",
+            "validate",
+            dir,
+            "--exclude",
+            "invalid.json",
+        ];
+        run(&args.map(String::from)).await.unwrap();
+        let args = ["report-size-deltas", "--include", "*.txt", "validate", dir];
+        run(&args.map(String::from)).await.unwrap();
+        let args = ["report-size-deltas", "validate", dir];
+        assert!(run(&args.map(String::from)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn discovery_options() {
+        let comment = run_to_file(&[
+            "--sketches-reports-source",
+            "tests/test_assets",
+            "--exclude",
+            "arduino-sam-*.json,arduino-samd-*.json",
+            "--max-depth",
+            "0",
+        ])
+        .await
+        .unwrap();
+        assert!(comment.contains("### `arduino:avr:leonardo`"));
+        // excluded and nested reports are not included
+        for board in ["arduino:sam:", "arduino:samd:", "arduino:avr:nano"] {
+            assert!(!comment.contains(board), "{board}");
+        }
+
+        let args = [
+            "--sketches-reports-source",
+            "tests/test_assets",
+            "--include",
+            "[",
+        ];
+        assert!(run_to_file(&args).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn stdin_source() {
+        let mut input = String::new();
+        for entry in fs::read_dir("tests/size-deltas-reports-new").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                input.push_str(&fs::read_to_string(path).unwrap());
+                input.push('\n');
+            }
+        }
+        let comment = run_to_file_with_input(&["--sketches-reports-source", "-"], input.as_bytes())
+            .await
+            .unwrap();
+        assert_eq!(
+            comment,
+            fs::read_to_string("tests/size-deltas-reports-new/out.md").unwrap()
+        );
+
+        let args = ["report-size-deltas", "validate", "-"].map(String::from);
+        run_with_input(&args, input.as_bytes()).await.unwrap();
+        let err = run_with_input(&args, &br#"{"boards": []}"#[..])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1 report(s) do not match the JSON schema (version 1)"
+        );
+    }
+
+    #[test]
+    fn annotation_message() {
+        let annotation = regression_annotation(&Regression {
+            board: "arduino:samd:mkrzero".to_string(),
+            sketch: "examples/Foo".to_string(),
+            file: "examples/Foo/Foo.ino".to_string(),
+            memory: MemoryKind::Flash,
+            delta: 84,
+            severity: Severity::Error,
+        });
+        assert_eq!(annotation.severity, AnnotationLevel::Error);
+        assert_eq!(annotation.path, "examples/Foo/Foo.ino");
+        assert_eq!(annotation.title.as_deref(), Some("Increased flash usage"));
+        assert_eq!(
+            annotation.message,
+            "Compiling examples/Foo for arduino:samd:mkrzero uses +84 bytes more flash"
+        );
+    }
 }
@@ -0,0 +1,230 @@
+//! A module to convert the JSON output of `arduino-cli compile --format json`
+//! into a [`Report`].
+//!
+//! Unlike the artifacts produced by [arduino/compile-sketches], arduino-cli only describes
+//! a single compilation. So, the output of a "base" build (before the changes) is paired
+//! with the output of a "head" build (after the changes) of the same sketch for the same board.
+//!
+//! [arduino/compile-sketches]: https://github.com/arduino/compile-sketches
+use super::{
+    builds::{MemoryUsage, add_sketch, compare, pair_files},
+    structs::Report,
+};
+use crate::{CommentAssemblyError, JsonError};
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+/// The output of `arduino-cli compile --format json`.
+///
+/// Only the fields needed to form a [`Report`] are parsed.
+#[derive(Debug, Deserialize, Default)]
+pub struct CompileOutput {
+    /// Was the sketch successfully compiled?
+    #[serde(default)]
+    pub success: bool,
+
+    /// The result of the build.
+    #[serde(default)]
+    pub builder_result: BuilderResult,
+}
+
+/// The `builder_result` in a [`CompileOutput`].
+#[derive(Debug, Deserialize, Default)]
+pub struct BuilderResult {
+    /// The size of each section in the compiled binary.
+    #[serde(default)]
+    pub executable_sections_size: Vec<ExecutableSectionSize>,
+
+    /// The build properties as a list of `key=value` pairs.
+    #[serde(default)]
+    pub build_properties: Vec<String>,
+}
+
+/// The size of a section in the compiled binary (see [`BuilderResult::executable_sections_size`]).
+#[derive(Debug, Deserialize, Default)]
+pub struct ExecutableSectionSize {
+    /// The section's name.
+    ///
+    /// Typically, `text` is the flash memory and `data` is the RAM for global variables.
+    pub name: String,
+
+    /// The section's size in bytes.
+    pub size: i64,
+
+    /// The section's maximum size in bytes (or `0` if unknown).
+    #[serde(default)]
+    pub max_size: u64,
+}
+
+impl CompileOutput {
+    /// Get the value of a build property (from [`BuilderResult::build_properties`]).
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.builder_result
+            .build_properties
+            .iter()
+            .find_map(|p| p.strip_prefix(key)?.strip_prefix('='))
+    }
+
+    /// Get the board's "Fully Qualified Board Name" (FQBN) from the `build.fqbn` property.
+    pub fn fqbn(&self) -> Option<&str> {
+        self.property("build.fqbn")
+    }
+
+    /// Get the path to the compiled sketch from the `build.source.path` property.
+    ///
+    /// The path is made relative to the given `root` (typically the repository's root folder).
+    /// If the sketch is outside the `root`, then only the sketch's folder name is returned.
+    pub fn sketch_name(&self, root: &Path) -> Option<String> {
+        let source = Path::new(self.property("build.source.path")?);
+        let name = source
+            .strip_prefix(root)
+            .ok()
+            .or_else(|| source.file_name().map(Path::new))?;
+        Some(name.to_string_lossy().replace('\\', "/"))
+    }
+
+    /// Get the usage of a memory kind.
+    ///
+    /// The `flash` memory is the `text` section, and the RAM is the `data` section.
+    fn usage(&self, flash: bool) -> Option<MemoryUsage> {
+        let names: &[&str] = if flash {
+            &["text", "flash"]
+        } else {
+            &["data", "ram"]
+        };
+        self.builder_result
+            .executable_sections_size
+            .iter()
+            .find(|s| names.contains(&s.name.to_lowercase().as_str()))
+            .map(|s| MemoryUsage {
+                used: s.size,
+                maximum: s.max_size,
+            })
+    }
+}
+
+/// Deserialize the output of `arduino-cli compile --format json` from the file at `path`.
+fn parse_compile_output<P: AsRef<Path>>(path: P) -> Result<CompileOutput, JsonError> {
+    let asset = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&asset)?)
+}
+
+/// Parse the JSON output of `arduino-cli compile --format json` into a [`Report`].
+///
+/// The `base` and `head` paths can both be single files or both be directories
+/// (scanned recursively for JSON files). Files in the `head` directory are paired with
+/// the file at the same relative path in the `base` directory.
+/// Head builds without a base build (like a new sketch) are reported without previous sizes.
+///
+/// The board's FQBN and the sketch's name are taken from the head build's properties.
+/// The sketch's name is its path (the `build.source.path` property) relative to the given `root`
+/// folder. Sketches outside the `root` folder are named after their folder.
+/// The [`Report::commit_hash`] and [`Report::commit_url`] are left empty
+/// because arduino-cli does not know about them.
+pub fn parse_arduino_cli<P: AsRef<Path>>(
+    base: P,
+    head: P,
+    root: &Path,
+) -> Result<Report, CommentAssemblyError> {
+    let pairs = pair_files(base.as_ref(), head.as_ref(), |path| {
+        path.extension()
+            .is_some_and(|ext| ext.to_string_lossy() == "json")
+    })?;
+    let mut report = Report::default();
+    for (base_path, head_path) in pairs {
+        let base = base_path.map(parse_compile_output).transpose()?;
+        let head = parse_compile_output(&head_path)?;
+        let (Some(fqbn), Some(name)) = (head.fqbn(), head.sketch_name(root)) else {
+            log::warn!("Skipping {head_path:?} since it does not specify the FQBN or sketch path.");
+            continue;
+        };
+        let (sketch, sizes) = compare(
+            name,
+            head.success,
+            [base.as_ref().and_then(|b| b.usage(true)), head.usage(true)],
+            [
+                base.as_ref().and_then(|b| b.usage(false)),
+                head.usage(false),
+            ],
+        );
+        add_sketch(&mut report, fqbn, sketch, sizes);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::{parse_arduino_cli, parse_compile_output};
+    use crate::report_structs::{SizeValue, SketchSizeKind};
+    use std::path::Path;
+
+    /// The folder that the sketches were compiled in (see the `build.source.path` property).
+    const ROOT: &str = "/home/runner/work/lib/lib";
+
+    #[test]
+    fn build_properties() {
+        let output = parse_compile_output("tests/arduino-cli/head/blink-uno.json").unwrap();
+        assert_eq!(output.fqbn(), Some("arduino:avr:uno"));
+        assert_eq!(output.property("build.project_name"), Some("Blink.ino"));
+        assert_eq!(output.property("build.project"), None);
+        let name = |root| output.sketch_name(Path::new(root)).unwrap();
+        assert_eq!(name(ROOT), "examples/Blink");
+        assert_eq!(name("/home/runner/work/lib/lib/"), "examples/Blink");
+        // sketches outside the root are named after their folder
+        assert_eq!(name("/elsewhere"), "Blink");
+    }
+
+    #[test]
+    fn directories() {
+        let report = parse_arduino_cli(
+            "tests/arduino-cli/base",
+            "tests/arduino-cli/head",
+            Path::new(ROOT),
+        )
+        .unwrap();
+        assert!(report.is_valid());
+        let boards = report
+            .boards
+            .iter()
+            .map(|b| (b.board.as_str(), b.sketches.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            boards,
+            [("arduino:samd:mkrzero", 1), ("arduino:avr:uno", 3)]
+        );
+
+        // the head build of examples/Button has no base build
+        let button = &report.boards[1].sketches[1];
+        assert_eq!(button.name, "examples/Button");
+        let size = button.sizes[0].get_size();
+        assert_eq!(size.current.absolute, SizeValue::Known(1200));
+        assert_eq!(
+            size.previous.as_ref().unwrap().absolute,
+            SizeValue::NotApplicable
+        );
+        assert_eq!(size.get_delta().absolute, SizeValue::NotApplicable);
+    }
+
+    #[test]
+    fn single_files() {
+        let report = parse_arduino_cli(
+            "tests/arduino-cli/base/fade-uno.json",
+            "tests/arduino-cli/head/blink-uno.json",
+            Path::new(ROOT),
+        )
+        .unwrap();
+        assert_eq!(report.boards.len(), 1);
+        let sketch = &report.boards[0].sketches[0];
+        assert_eq!(sketch.name, "examples/Blink");
+        assert!(sketch.compilation_success);
+        let SketchSizeKind::Flash { size } = &sketch.sizes[0] else {
+            unreachable!()
+        };
+        let delta = size.get_delta();
+        assert_eq!(delta.absolute, SizeValue::Known(956 - 1100));
+        assert_eq!(delta.relative, Some(SizeValue::Known(-0.45)));
+        assert_eq!(size.maximum, Some(SizeValue::Known(32256)));
+    }
+}
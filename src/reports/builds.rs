@@ -0,0 +1,184 @@
+//! A module of helpers to form a [`Report`](super::structs::Report) by comparing
+//! the output of a "base" build (before the changes) with the output of
+//! a "head" build (after the changes).
+//!
+//! This is used for build tools that only describe a single compilation
+//! (see [`parse_arduino_cli()`](super::parse_arduino_cli),
+//! [`parse_platformio()`](super::parse_platformio) and [`parse_elf()`](super::parse_elf)).
+use super::structs::{
+    Board, BoardSize, ComponentSize, Report, SizeValue, Sketch, SketchDeltaSize, SketchSize,
+    SketchSizeKind,
+};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The memory usage of a single build.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(super) struct MemoryUsage {
+    /// The used memory in bytes.
+    pub used: i64,
+
+    /// The memory's capacity in bytes (or `0` if unknown).
+    pub maximum: u64,
+}
+
+/// Get the `size` of a memory kind relative to its `maximum` (in percent).
+///
+/// Like arduino/compile-sketches, this is rounded to 2 decimal places.
+pub(super) fn relative(size: i64, maximum: u64) -> SizeValue<f32> {
+    if maximum == 0 {
+        return SizeValue::NotApplicable;
+    }
+    let percent = size as f64 * 100.0 / maximum as f64;
+    SizeValue::Known(((percent * 100.0).round() / 100.0) as f32)
+}
+
+/// Get the [`SketchSize`] of a memory kind from the usage of a `base` and `head` build.
+fn sketch_size(base: Option<MemoryUsage>, head: Option<MemoryUsage>) -> SketchSize {
+    let maximum = head.or(base).map(|u| u.maximum).unwrap_or_default();
+    let size = |usage: Option<MemoryUsage>| match usage {
+        Some(u) => SketchDeltaSize {
+            absolute: SizeValue::Known(u.used),
+            relative: Some(relative(u.used, maximum)),
+        },
+        None => SketchDeltaSize {
+            absolute: SizeValue::NotApplicable,
+            relative: Some(SizeValue::NotApplicable),
+        },
+    };
+    let delta = match (base, head) {
+        (Some(base), Some(head)) => SketchDeltaSize {
+            absolute: SizeValue::Known(head.used - base.used),
+            relative: Some(relative(head.used - base.used, maximum)),
+        },
+        _ => size(None),
+    };
+    SketchSize {
+        maximum: Some(if maximum > 0 {
+            SizeValue::Known(maximum)
+        } else {
+            SizeValue::NotApplicable
+        }),
+        current: size(head),
+        previous: Some(size(base)),
+        delta: Some(delta),
+    }
+}
+
+/// Form a [`Sketch`] (and the board's [`BoardSize`]s) from the `flash` and `ram` usage
+/// of a base and head build (in that order).
+pub(super) fn compare(
+    name: String,
+    compilation_success: bool,
+    flash: [Option<MemoryUsage>; 2],
+    ram: [Option<MemoryUsage>; 2],
+) -> (Sketch, Vec<BoardSize>) {
+    let [base, head] = flash;
+    let flash = sketch_size(base, head);
+    let [base, head] = ram;
+    let ram = sketch_size(base, head);
+    let sizes = vec![
+        BoardSize::Flash {
+            maximum: flash.maximum,
+            delta: None,
+        },
+        BoardSize::Ram {
+            maximum: ram.maximum,
+            delta: None,
+        },
+    ];
+    let sketch = Sketch {
+        name,
+        compilation_success,
+        sizes: vec![
+            SketchSizeKind::Flash { size: flash },
+            SketchSizeKind::Ram { size: ram },
+        ],
+        warnings: None,
+        breakdown: None,
+    };
+    (sketch, sizes)
+}
+
+/// Compare the sizes of named components (symbols or object files) in a base and head build.
+///
+/// Components whose size did not change are omitted. The result is sorted from
+/// the largest increase to the largest decrease (then by name).
+pub(super) fn diff_components(
+    base: &BTreeMap<String, u64>,
+    head: &BTreeMap<String, u64>,
+) -> Vec<ComponentSize> {
+    let mut components = base
+        .keys()
+        .chain(head.keys().filter(|name| !base.contains_key(*name)))
+        .map(|name| ComponentSize {
+            name: name.clone(),
+            previous: base.get(name).copied().unwrap_or_default(),
+            current: head.get(name).copied().unwrap_or_default(),
+        })
+        .filter(|c| c.delta() != 0)
+        .collect::<Vec<_>>();
+    components.sort_by(|a, b| b.delta().cmp(&a.delta()).then_with(|| a.name.cmp(&b.name)));
+    components
+}
+
+/// Add the given `sketch` to the `board` in the given `report`.
+///
+/// The board (with its `sizes`) is added to the `report` if it is not already present.
+pub(super) fn add_sketch(report: &mut Report, board: &str, sketch: Sketch, sizes: Vec<BoardSize>) {
+    match report.boards.iter_mut().find(|b| b.board == board) {
+        Some(board) => board.sketches.push(sketch),
+        None => report.boards.push(Board {
+            board: board.to_string(),
+            sketches: vec![sketch],
+            sizes: Some(sizes),
+        }),
+    }
+}
+
+/// Recursively scan the directory at `path` for files that satisfy the `filter`.
+///
+/// The files are keyed by their path relative to the given `root` directory.
+fn find_files(
+    path: &Path,
+    root: &Path,
+    filter: &impl Fn(&Path) -> bool,
+) -> Result<BTreeMap<PathBuf, PathBuf>, std::io::Error> {
+    let mut files = BTreeMap::new();
+    for entry in fs::read_dir(path)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(find_files(&path, root, filter)?);
+        } else if filter(&path) {
+            let key = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+            files.insert(key, path);
+        }
+    }
+    Ok(files)
+}
+
+/// Pair the files of the `base` builds with the files of the `head` builds.
+///
+/// The `base` and `head` paths can both be single files or both be directories
+/// (scanned recursively for files that satisfy the `filter`).
+/// Files in the `head` directory are paired with the file at the same relative path
+/// in the `base` directory. Head builds without a base build (like a new sketch)
+/// are paired with [`None`].
+pub(super) fn pair_files(
+    base: &Path,
+    head: &Path,
+    filter: impl Fn(&Path) -> bool,
+) -> Result<Vec<(Option<PathBuf>, PathBuf)>, std::io::Error> {
+    if base.is_file() && head.is_file() {
+        // single files are paired regardless of their names
+        return Ok(vec![(Some(base.to_path_buf()), head.to_path_buf())]);
+    }
+    let base_files = find_files(base, base, &filter)?;
+    Ok(find_files(head, head, &filter)?
+        .into_iter()
+        .map(|(key, head_path)| (base_files.get(&key).cloned(), head_path))
+        .collect())
+}
@@ -0,0 +1,262 @@
+//! A module to discover the report files (in a folder) that shall be parsed.
+//!
+//! Files are matched by glob patterns relative to the scanned folder
+//! (using `/` as the path separator on all platforms):
+//!
+//! - `*` matches any characters except `/`
+//! - `**` matches any number of nested folders (including none)
+//! - `?` matches any single character except `/`
+//! - `{a,b}` matches either `a` or `b`
+use crate::CommentAssemblyError;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The glob pattern of the files included by default.
+pub const DEFAULT_INCLUDE: &str = "**/*.json";
+
+/// The options used to discover report files in a folder
+/// (see [`parse_artifacts_with()`](super::parse_artifacts_with)).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveryOptions {
+    /// The glob patterns of the files to include (see [`DEFAULT_INCLUDE`]).
+    ///
+    /// A file is included if it matches any of these patterns.
+    pub include: Vec<String>,
+
+    /// The glob patterns of the files (or folders) to exclude.
+    ///
+    /// This takes precedence over [`DiscoveryOptions::include`].
+    /// Folders that match any of these patterns are not scanned.
+    pub exclude: Vec<String>,
+
+    /// The maximum number of nested folders to scan (if any).
+    ///
+    /// Use `0` to only include the files directly in the scanned folder.
+    pub max_depth: Option<usize>,
+
+    /// Follow symbolic links to files and folders?
+    ///
+    /// Each folder is only scanned once, so symbolic links that form a loop are not followed.
+    /// If disabled, then all symbolic links are skipped.
+    pub follow_symlinks: bool,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        Self {
+            include: vec![DEFAULT_INCLUDE.to_string()],
+            exclude: vec![],
+            max_depth: None,
+            follow_symlinks: true,
+        }
+    }
+}
+
+/// Compile the given glob `patterns` into a [`GlobSet`].
+fn glob_set(patterns: &[String]) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(GlobBuilder::new(pattern).literal_separator(true).build()?);
+    }
+    builder.build()
+}
+
+/// The state shared while scanning the folders.
+struct Scan<'a> {
+    root: &'a Path,
+    options: &'a DiscoveryOptions,
+    include: GlobSet,
+    exclude: GlobSet,
+    visited: HashSet<PathBuf>,
+    files: Vec<PathBuf>,
+}
+
+impl Scan<'_> {
+    /// Recursively scan the given `folder` (nested `depth` folders in the root folder).
+    ///
+    /// The folder's entries are visited in order of their names.
+    fn folder(&mut self, folder: &Path, depth: usize) -> Result<(), std::io::Error> {
+        if !self.visited.insert(fs::canonicalize(folder)?) {
+            log::debug!("Skipping already scanned folder: {}", folder.display());
+            return Ok(());
+        }
+        let mut entries = fs::read_dir(folder)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort();
+        for path in entries {
+            let relative = path
+                .strip_prefix(self.root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            if !self.options.follow_symlinks && fs::symlink_metadata(&path)?.is_symlink() {
+                log::debug!("Skipping symbolic link: {relative}");
+            } else if self.exclude.is_match(&relative) {
+                log::debug!("Skipping excluded path: {relative}");
+            } else if path.is_dir() {
+                if self.options.max_depth.is_some_and(|max| depth >= max) {
+                    log::debug!("Skipping folder beyond the maximum depth: {relative}");
+                } else {
+                    self.folder(&path, depth + 1)?;
+                }
+            } else if self.include.is_match(&relative) {
+                self.files.push(path);
+            } else {
+                log::debug!("Ignoring file that is not included: {relative}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Find the files in the given `root` folder that satisfy the given `options`.
+///
+/// The files are sorted by their path (folders are scanned in order of their names),
+/// so the result does not depend on the order in which the file system lists them.
+pub(crate) fn discover(
+    root: &Path,
+    options: &DiscoveryOptions,
+) -> Result<Vec<PathBuf>, CommentAssemblyError> {
+    let mut scan = Scan {
+        root,
+        options,
+        include: glob_set(&options.include)?,
+        exclude: glob_set(&options.exclude)?,
+        visited: HashSet::new(),
+        files: vec![],
+    };
+    scan.folder(root, 0)?;
+    Ok(scan.files)
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::{DiscoveryOptions, discover};
+    use crate::CommentAssemblyError;
+    use std::{fs, path::Path};
+
+    fn names(root: &Path, options: &DiscoveryOptions) -> Vec<String> {
+        discover(root, options)
+            .unwrap()
+            .iter()
+            .map(|p| {
+                p.strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect()
+    }
+
+    /// Create a folder with nested report files (and other files).
+    fn setup() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for file in [
+            "z.json",
+            "a.json",
+            "notes.txt",
+            "sketches-reports_uno/report.json",
+            "sketches-reports_uno/library.json",
+            "sketches-reports_nano/deep/report.json",
+            "other/report.json",
+        ] {
+            let path = dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "{}").unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn default_options() {
+        let dir = setup();
+        assert_eq!(
+            names(dir.path(), &DiscoveryOptions::default()),
+            [
+                "a.json",
+                "other/report.json",
+                "sketches-reports_nano/deep/report.json",
+                "sketches-reports_uno/library.json",
+                "sketches-reports_uno/report.json",
+                "z.json",
+            ]
+        );
+    }
+
+    #[test]
+    fn include_exclude() {
+        let dir = setup();
+        let options = DiscoveryOptions {
+            include: vec!["sketches-reports_*/**/*.json".to_string()],
+            exclude: vec!["**/library.json".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            names(dir.path(), &options),
+            [
+                "sketches-reports_nano/deep/report.json",
+                "sketches-reports_uno/report.json",
+            ]
+        );
+        // excluded folders are not scanned
+        let options = DiscoveryOptions {
+            exclude: vec!["sketches-reports_*".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            names(dir.path(), &options),
+            ["a.json", "other/report.json", "z.json"]
+        );
+    }
+
+    #[test]
+    fn max_depth() {
+        let dir = setup();
+        let options = DiscoveryOptions {
+            max_depth: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(names(dir.path(), &options), ["a.json", "z.json"]);
+        let options = DiscoveryOptions {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(names(dir.path(), &options).len(), 5);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks() {
+        let dir = setup();
+        // a link to a file and a link that forms a loop
+        std::os::unix::fs::symlink(dir.path().join("a.json"), dir.path().join("b.json")).unwrap();
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("other/loop")).unwrap();
+        let followed = names(dir.path(), &DiscoveryOptions::default());
+        assert_eq!(followed.len(), 7);
+        assert_eq!(followed[1], "b.json");
+        let options = DiscoveryOptions {
+            follow_symlinks: false,
+            ..Default::default()
+        };
+        assert_eq!(names(dir.path(), &options).len(), 6);
+    }
+
+    #[test]
+    fn bad_pattern() {
+        let options = DiscoveryOptions {
+            include: vec!["[".to_string()],
+            ..Default::default()
+        };
+        assert!(matches!(
+            discover(Path::new("tests"), &options),
+            Err(CommentAssemblyError::Pattern(_))
+        ));
+    }
+}
@@ -0,0 +1,693 @@
+//! A module to compute the memory usage of compiled sketches from their ELF files.
+//!
+//! Only the ELF header and section headers are parsed. Like the `recipe.size.regex`
+//! and `recipe.size.regex.data` properties of Arduino platforms, the sizes of certain
+//! sections (by name) are summed to get the flash and RAM usage.
+//! The sections counted depend on the [`Architecture`] of the ELF file.
+//!
+//! Like the output of arduino-cli, a "base" build (before the changes) is paired
+//! with a "head" build (after the changes) of the same sketch for the same board.
+use super::{
+    builds::{MemoryUsage, add_sketch, compare, diff_components, pair_files},
+    linker_map::{InputSection, compare_libraries, object_sizes, parse_linker_map},
+    structs::{Report, SizeBreakdown},
+};
+use crate::{CommentAssemblyError, ElfError};
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// The `e_machine` value of AVR microcontrollers.
+const EM_AVR: u16 = 83;
+
+/// The `e_machine` value of 32-bit ARM processors (including Cortex-M).
+const EM_ARM: u16 = 40;
+
+/// The `e_machine` value of Xtensa processors (ESP32, ESP8266).
+const EM_XTENSA: u16 = 94;
+
+/// The `e_machine` value of RISC-V processors.
+const EM_RISCV: u16 = 243;
+
+/// The section type of a symbol table.
+const SHT_SYMTAB: u32 = 2;
+
+/// The symbol type of a variable.
+const STT_OBJECT: u8 = 1;
+
+/// The symbol type of a function.
+const STT_FUNC: u8 = 2;
+
+/// The section index of an undefined symbol.
+const SHN_UNDEF: u16 = 0;
+
+/// The start of reserved section indices (for absolute and common symbols).
+const SHN_LORESERVE: u16 = 0xFF00;
+
+/// The CPU architecture that an ELF file was compiled for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Architecture {
+    /// AVR microcontrollers (`arduino:avr`).
+    Avr,
+
+    /// ARM Cortex-M microcontrollers (`arduino:samd`, `arduino:mbed`, etc.).
+    Arm,
+
+    /// Xtensa microcontrollers (`esp32:esp32`, `esp8266:esp8266`).
+    Xtensa,
+
+    /// RISC-V microcontrollers (for example, ESP32-C3).
+    RiscV,
+
+    /// Any other architecture (identified by the ELF header's `e_machine` value).
+    Other(u16),
+}
+
+impl Architecture {
+    fn from_machine(machine: u16) -> Self {
+        match machine {
+            EM_AVR => Self::Avr,
+            EM_ARM => Self::Arm,
+            EM_XTENSA => Self::Xtensa,
+            EM_RISCV => Self::RiscV,
+            other => Self::Other(other),
+        }
+    }
+
+    /// The architecture's name (as used in Arduino platforms' identifiers).
+    pub fn name(&self) -> String {
+        match self {
+            Self::Avr => "avr".to_string(),
+            Self::Arm => "arm".to_string(),
+            Self::Xtensa => "xtensa".to_string(),
+            Self::RiscV => "riscv".to_string(),
+            Self::Other(machine) => format!("elf-machine-{machine}"),
+        }
+    }
+
+    /// The names of sections that occupy flash memory.
+    ///
+    /// These mirror the `recipe.size.regex` of the respective Arduino platforms.
+    fn flash_sections(&self) -> &'static [&'static str] {
+        match self {
+            Self::Avr => &[".text", ".data", ".bootloader"],
+            Self::Arm => &[".text", ".data", ".rodata", ".ARM.exidx", ".ARM.extab"],
+            Self::Xtensa => &[
+                // ESP32
+                ".iram0.text",
+                ".iram0.vectors",
+                ".dram0.data",
+                ".flash.text",
+                ".flash.rodata",
+                ".flash.appdesc",
+                ".flash.init_array",
+                ".eh_frame",
+                // ESP8266
+                ".irom0.text",
+                ".text",
+                ".text1",
+                ".data",
+                ".rodata",
+            ],
+            Self::RiscV => &[
+                // ESP32-C3 and similar
+                ".iram0.text",
+                ".iram0.vectors",
+                ".dram0.data",
+                ".flash.text",
+                ".flash.rodata",
+                ".flash.appdesc",
+                ".flash.init_array",
+                ".eh_frame",
+                // generic
+                ".text",
+                ".data",
+                ".sdata",
+                ".rodata",
+                ".srodata",
+            ],
+            Self::Other(_) => &[".text", ".data", ".rodata"],
+        }
+    }
+
+    /// The names of sections that occupy RAM (for global variables).
+    ///
+    /// These mirror the `recipe.size.regex.data` of the respective Arduino platforms.
+    fn ram_sections(&self) -> &'static [&'static str] {
+        match self {
+            Self::Avr => &[".data", ".bss", ".noinit"],
+            Self::Arm => &[".data", ".bss", ".noinit"],
+            Self::Xtensa => &[
+                // ESP32
+                ".dram0.data",
+                ".dram0.bss",
+                ".noinit",
+                // ESP8266
+                ".data",
+                ".rodata",
+                ".bss",
+            ],
+            Self::RiscV => &[
+                ".dram0.data",
+                ".dram0.bss",
+                ".noinit",
+                ".data",
+                ".sdata",
+                ".bss",
+                ".sbss",
+            ],
+            Self::Other(_) => &[".data", ".bss"],
+        }
+    }
+}
+
+/// A section header in an [`ElfFile`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// The section's name.
+    pub name: String,
+
+    /// The section's type (`sh_type`).
+    pub kind: u32,
+
+    /// The section's size in bytes.
+    pub size: u64,
+
+    /// The offset of the section's content in the file.
+    offset: u64,
+
+    /// The index of an associated section (for symbol tables, the string table).
+    link: u32,
+
+    /// The size of each entry in the section (for symbol tables, each symbol).
+    entry_size: u64,
+}
+
+/// A function or variable in an [`ElfFile`]'s symbol table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// The symbol's (possibly mangled) name.
+    pub name: String,
+
+    /// The symbol's size in bytes.
+    pub size: u64,
+
+    /// The index of the [`Section`] that contains the symbol.
+    pub section: usize,
+}
+
+/// The parsed header and section headers of an ELF file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElfFile {
+    /// The architecture that the ELF file was compiled for.
+    pub architecture: Architecture,
+
+    /// The sections in the ELF file (in order of their section header index).
+    pub sections: Vec<Section>,
+
+    /// The functions and variables in the ELF file's symbol table (if not stripped).
+    pub symbols: Vec<Symbol>,
+}
+
+/// Add the given `offset` to the `base` offset, failing if the sum overflows.
+fn add(base: u64, offset: u64) -> Result<u64, ElfError> {
+    base.checked_add(offset).ok_or(ElfError::Truncated)
+}
+
+/// The offset of the entry at `index` in a table at `base` with entries of the given `size`.
+fn entry(base: u64, index: u64, size: u64) -> Result<u64, ElfError> {
+    index
+        .checked_mul(size)
+        .and_then(|offset| base.checked_add(offset))
+        .ok_or(ElfError::Truncated)
+}
+
+/// A bounds-checked reader of the integers in an ELF file.
+struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+    is_64: bool,
+}
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&self, offset: u64) -> Result<[u8; N], ElfError> {
+        let start = usize::try_from(offset).map_err(|_| ElfError::Truncated)?;
+        self.data
+            .get(start..start.saturating_add(N))
+            .and_then(|b| b.try_into().ok())
+            .ok_or(ElfError::Truncated)
+    }
+
+    fn u16(&self, offset: u64) -> Result<u16, ElfError> {
+        let bytes = self.bytes(offset)?;
+        Ok(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: u64) -> Result<u32, ElfError> {
+        let bytes = self.bytes(offset)?;
+        Ok(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    fn u64(&self, offset: u64) -> Result<u64, ElfError> {
+        let bytes = self.bytes(offset)?;
+        Ok(if self.big_endian {
+            u64::from_be_bytes(bytes)
+        } else {
+            u64::from_le_bytes(bytes)
+        })
+    }
+
+    /// Read an address-sized word (32 or 64 bits depending on the ELF class).
+    fn word(&self, offset: u64) -> Result<u64, ElfError> {
+        if self.is_64 {
+            self.u64(offset)
+        } else {
+            self.u32(offset).map(u64::from)
+        }
+    }
+
+    /// Read a null-terminated string.
+    fn string(&self, offset: u64) -> Result<String, ElfError> {
+        let start = usize::try_from(offset).map_err(|_| ElfError::Truncated)?;
+        let bytes = self.data.get(start..).ok_or(ElfError::Truncated)?;
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        Ok(String::from_utf8_lossy(&bytes[..end]).into_owned())
+    }
+}
+
+impl ElfFile {
+    /// Parse the header, section headers and symbol table of the ELF file in the given `data`.
+    pub fn parse(data: &[u8]) -> Result<Self, ElfError> {
+        if data.get(..4) != Some(b"\x7fELF".as_slice()) {
+            return Err(ElfError::NotElf);
+        }
+        let is_64 = match data.get(4) {
+            Some(1) => false,
+            Some(2) => true,
+            _ => return Err(ElfError::Unsupported("ELF class")),
+        };
+        let big_endian = match data.get(5) {
+            Some(1) => false,
+            Some(2) => true,
+            _ => return Err(ElfError::Unsupported("data encoding")),
+        };
+        let reader = Reader {
+            data,
+            big_endian,
+            is_64,
+        };
+        let architecture = Architecture::from_machine(reader.u16(18)?);
+        let (sh_offset, sh_entry_size, sh_count, sh_names) = if is_64 {
+            (
+                reader.u64(0x28)?,
+                reader.u16(0x3A)?,
+                reader.u16(0x3C)?,
+                reader.u16(0x3E)?,
+            )
+        } else {
+            (
+                u64::from(reader.u32(0x20)?),
+                reader.u16(0x2E)?,
+                reader.u16(0x30)?,
+                reader.u16(0x32)?,
+            )
+        };
+        let header = |index: u16| entry(sh_offset, index.into(), sh_entry_size.into());
+        // offsets of the `sh_offset`, `sh_size`, `sh_link` and `sh_entsize` fields
+        let (offset_field, size_field, link_field, entry_size_field) = if is_64 {
+            (24, 32, 40, 56)
+        } else {
+            (16, 20, 24, 36)
+        };
+        let names_offset = reader.word(add(header(sh_names)?, offset_field)?)?;
+        let mut sections = Vec::with_capacity(usize::from(sh_count));
+        for index in 0..sh_count {
+            let header = header(index)?;
+            sections.push(Section {
+                name: reader.string(add(names_offset, reader.u32(header)?.into())?)?,
+                kind: reader.u32(add(header, 4)?)?,
+                size: reader.word(add(header, size_field)?)?,
+                offset: reader.word(add(header, offset_field)?)?,
+                link: reader.u32(add(header, link_field)?)?,
+                entry_size: reader.word(add(header, entry_size_field)?)?,
+            });
+        }
+        let symbols = Self::parse_symbols(&reader, &sections)?;
+        Ok(Self {
+            architecture,
+            sections,
+            symbols,
+        })
+    }
+
+    /// Parse the functions and variables in the symbol table (`.symtab`) of the given `sections`.
+    ///
+    /// Undefined, absolute and common symbols are skipped, as are symbols without a size.
+    fn parse_symbols(reader: &Reader, sections: &[Section]) -> Result<Vec<Symbol>, ElfError> {
+        let Some(table) = sections.iter().find(|s| s.kind == SHT_SYMTAB) else {
+            return Ok(vec![]);
+        };
+        let names = sections
+            .get(table.link as usize)
+            .ok_or(ElfError::Truncated)?
+            .offset;
+        let entry_size = match table.entry_size {
+            0 if reader.is_64 => 24,
+            0 => 16,
+            size => size,
+        };
+        // offsets of the `st_info`, `st_shndx` and `st_size` fields in a symbol
+        let (info_field, section_field, size_field) = if reader.is_64 {
+            (4, 6, 16)
+        } else {
+            (12, 14, 8)
+        };
+        let mut symbols = vec![];
+        for index in 1..table.size / entry_size {
+            let entry = entry(table.offset, index, entry_size)?;
+            let [info] = reader.bytes::<1>(add(entry, info_field)?)?;
+            let section = reader.u16(add(entry, section_field)?)?;
+            let size = reader.word(add(entry, size_field)?)?;
+            if !matches!(info & 0xF, STT_OBJECT | STT_FUNC)
+                || section == SHN_UNDEF
+                || section >= SHN_LORESERVE
+                || size == 0
+            {
+                continue;
+            }
+            symbols.push(Symbol {
+                name: reader.string(add(names, reader.u32(entry)?.into())?)?,
+                size,
+                section: usize::from(section),
+            });
+        }
+        Ok(symbols)
+    }
+
+    /// Read and parse the ELF file at the given `path`.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, ElfError> {
+        Self::parse(&fs::read(path)?)
+    }
+
+    /// Sum the sizes of the sections with the given `names`.
+    fn sum_sections(&self, names: &[&str]) -> u64 {
+        self.sections
+            .iter()
+            .filter(|s| names.contains(&s.name.as_str()))
+            .map(|s| s.size)
+            .sum()
+    }
+
+    /// The flash memory used by the compiled program (in bytes).
+    pub fn flash_size(&self) -> u64 {
+        self.sum_sections(self.architecture.flash_sections())
+    }
+
+    /// The RAM used by global variables (in bytes).
+    pub fn ram_size(&self) -> u64 {
+        self.sum_sections(self.architecture.ram_sections())
+    }
+
+    /// Sum the sizes of the symbols in the sections that occupy flash memory or RAM.
+    ///
+    /// Symbols with the same name (for example, `static` variables in different files)
+    /// are summed together.
+    pub fn symbol_sizes(&self) -> BTreeMap<String, u64> {
+        let architecture = self.architecture;
+        let mut sizes = BTreeMap::new();
+        for symbol in &self.symbols {
+            let Some(section) = self.sections.get(symbol.section) else {
+                continue;
+            };
+            let name = section.name.as_str();
+            if architecture.flash_sections().contains(&name)
+                || architecture.ram_sections().contains(&name)
+            {
+                *sizes.entry(symbol.name.clone()).or_default() += symbol.size;
+            }
+        }
+        sizes
+    }
+
+    /// Get the memory usage of the `flash` (or RAM) memory.
+    ///
+    /// The maximum is unknown because it is not stored in the ELF file.
+    fn usage(&self, flash: bool) -> MemoryUsage {
+        let used = if flash {
+            self.flash_size()
+        } else {
+            self.ram_size()
+        };
+        MemoryUsage {
+            used: i64::try_from(used).unwrap_or(i64::MAX),
+            maximum: 0,
+        }
+    }
+}
+
+/// Get the board's identifier from the name of the folder containing an ELF file.
+///
+/// arduino-cli names the build folder after the board's FQBN with `:` replaced by `.`
+/// (for example, `arduino.avr.uno`). If the folder's name does not look like an FQBN,
+/// then the ELF file's [`Architecture::name()`] is used instead.
+fn board_name(path: &Path, elf: &ElfFile) -> String {
+    path.parent()
+        .and_then(|dir| dir.file_name())
+        .map(|name| name.to_string_lossy())
+        .filter(|name| name.split('.').count() >= 3)
+        .map(|name| name.replace('.', ":"))
+        .unwrap_or_else(|| elf.architecture.name())
+}
+
+/// Parse the input sections of the linker map file next to an ELF file
+/// (with the same name, but a `.map` extension).
+///
+/// Returns [`None`] if there is no such linker map file.
+fn linker_map(elf_path: &Path) -> Result<Option<Vec<InputSection>>, CommentAssemblyError> {
+    let map_path = elf_path.with_extension("map");
+    if !map_path.is_file() {
+        return Ok(None);
+    }
+    Ok(Some(parse_linker_map(&fs::read_to_string(map_path)?)))
+}
+
+/// Compare the symbols (and linked object files and libraries) of a base and head build.
+fn breakdown(
+    base: (&Path, &ElfFile),
+    head: (&Path, &ElfFile),
+) -> Result<SizeBreakdown, CommentAssemblyError> {
+    let mut breakdown = SizeBreakdown {
+        symbols: diff_components(&base.1.symbol_sizes(), &head.1.symbol_sizes()),
+        ..Default::default()
+    };
+    if let (Some(base_map), Some(head_map)) = (linker_map(base.0)?, linker_map(head.0)?) {
+        let architecture = head.1.architecture;
+        let (flash, ram) = (architecture.flash_sections(), architecture.ram_sections());
+        let mut outputs = flash.to_vec();
+        outputs.extend(ram);
+        breakdown.objects = diff_components(
+            &object_sizes(&base_map, &outputs),
+            &object_sizes(&head_map, &outputs),
+        );
+        breakdown.libraries = compare_libraries(&base_map, &head_map, flash, ram);
+    }
+    Ok(breakdown)
+}
+
+/// Compute the sizes of compiled sketches from pairs of ELF files into a [`Report`].
+///
+/// The `base` and `head` paths can both be single ELF files or both be directories
+/// (scanned recursively for `*.elf` files). Files in the `head` directory are paired with
+/// the file at the same relative path in the `base` directory.
+/// Head builds without a base build (like a new sketch) are reported without previous sizes.
+///
+/// The board is taken from the name of the folder containing the head build's ELF file
+/// (see the output of `arduino-cli compile --output-dir`), and the sketch's name is
+/// the ELF file's name without the `.ino.elf` extension.
+/// The maximum sizes are unknown, so relative changes are not reported.
+/// The [`Report::commit_hash`] and [`Report::commit_url`] are left empty.
+///
+/// The changes in size of each symbol are also reported in the [`Sketch::breakdown`].
+/// If a linker map file (`-Wl,-Map`) with the same name as the ELF file (but a `.map` extension)
+/// exists for both builds, then the changes in size of each object file and
+/// the flash and RAM usage of each library are also reported.
+///
+/// [`Sketch::breakdown`]: super::structs::Sketch::breakdown
+pub fn parse_elf<P: AsRef<Path>>(base: P, head: P) -> Result<Report, CommentAssemblyError> {
+    let pairs = pair_files(base.as_ref(), head.as_ref(), |path| {
+        path.extension()
+            .is_some_and(|ext| ext.to_string_lossy() == "elf")
+    })?;
+    let mut report = Report::default();
+    for (base_path, head_path) in pairs {
+        let base = base_path
+            .map(|path| ElfFile::read(&path).map(|elf| (path, elf)))
+            .transpose()?;
+        let head = ElfFile::read(&head_path)?;
+        let file_name = head_path.file_name().unwrap_or_default().to_string_lossy();
+        let name = file_name.trim_end_matches(".elf").trim_end_matches(".ino");
+        let usage = |flash| base.as_ref().map(|(_, elf)| elf.usage(flash));
+        let (mut sketch, sizes) = compare(
+            name.to_string(),
+            true,
+            [usage(true), Some(head.usage(true))],
+            [usage(false), Some(head.usage(false))],
+        );
+        // a new sketch has nothing to compare its symbols with
+        if let Some((base_path, base)) = &base {
+            let breakdown = breakdown((base_path, base), (&head_path, &head))?;
+            sketch.breakdown = Some(breakdown).filter(|b| !b.is_empty());
+        }
+        add_sketch(&mut report, &board_name(&head_path, &head), sketch, sizes);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::{Architecture, ElfFile, parse_elf};
+    use crate::{
+        ElfError,
+        report_structs::{SizeValue, SketchSizeKind},
+    };
+
+    #[test]
+    fn section_sizes() {
+        let elf = ElfFile::read("tests/elf/base/arduino.avr.uno/Blink.ino.elf").unwrap();
+        assert_eq!(elf.architecture, Architecture::Avr);
+        // .text + .data (debug info is not counted)
+        assert_eq!(elf.flash_size(), 924);
+        // .data + .bss
+        assert_eq!(elf.ram_size(), 9);
+
+        let elf = ElfFile::read("tests/elf/base/esp32.esp32.esp32/Blink.ino.elf").unwrap();
+        assert_eq!(elf.architecture, Architecture::Xtensa);
+        assert_eq!(elf.flash_size(), 1027 + 56000 + 13000 + 60000 + 150000);
+        assert_eq!(elf.ram_size(), 13000 + 8200);
+    }
+
+    #[test]
+    fn elf_64() {
+        let elf = ElfFile::read("tests/elf/base/riscv.generic.rv64/Blink.ino.elf").unwrap();
+        assert_eq!(elf.architecture, Architecture::RiscV);
+        assert_eq!(elf.flash_size(), 4096 + 512 + 16 + 64 + 8);
+        assert_eq!(elf.ram_size(), 64 + 8 + 8 + 128);
+    }
+
+    #[test]
+    fn symbols() {
+        let elf = ElfFile::read("tests/elf/head/riscv.generic.rv64/Blink.ino.elf").unwrap();
+        let sizes = elf.symbol_sizes();
+        assert_eq!(sizes["loop"], 40);
+        assert_eq!(sizes["counter"], 8);
+    }
+
+    #[test]
+    fn breakdown() {
+        let report = parse_elf(
+            "tests/elf/base/arduino.avr.uno/Blink.ino.elf",
+            "tests/elf/head/arduino.avr.uno/Blink.ino.elf",
+        )
+        .unwrap();
+        let breakdown = report.boards[0].sketches[0].breakdown.as_ref().unwrap();
+        assert_eq!(breakdown.symbols.len(), 1);
+        assert_eq!(breakdown.symbols[0].name, "loop");
+        assert_eq!(breakdown.symbols[0].delta(), 32);
+        // from the linker map files
+        assert_eq!(breakdown.objects.len(), 1);
+        assert_eq!(breakdown.objects[0].name, "Blink.ino.cpp.o");
+        assert_eq!(breakdown.objects[0].delta(), 32);
+        assert_eq!(breakdown.libraries.len(), 5);
+
+        // without linker map files
+        let report = parse_elf(
+            "tests/elf/base/arduino.samd.mkrzero/Blink.ino.elf",
+            "tests/elf/head/arduino.samd.mkrzero/Blink.ino.elf",
+        )
+        .unwrap();
+        let breakdown = report.boards[0].sketches[0].breakdown.as_ref().unwrap();
+        assert!(breakdown.objects.is_empty());
+        assert_eq!(breakdown.symbols.first().unwrap().name, "loop");
+        assert_eq!(breakdown.symbols.last().unwrap().name, "SerialUSB");
+    }
+
+    #[test]
+    fn not_elf() {
+        let result = ElfFile::parse(b"{\"not\": \"an ELF file\"}");
+        assert!(matches!(result, Err(ElfError::NotElf)));
+        let result = ElfFile::parse(b"\x7fELF\x01\x01\x01\x00");
+        assert!(matches!(result, Err(ElfError::Truncated)));
+    }
+
+    #[test]
+    fn offset_overflow() {
+        let mut data = vec![0; 64];
+        data[..6].copy_from_slice(b"\x7fELF\x02\x01");
+        // section headers at the very end of the address space
+        data[0x28..0x30].copy_from_slice(&u64::MAX.to_le_bytes());
+        data[0x3A..0x3C].copy_from_slice(&64u16.to_le_bytes());
+        data[0x3C..0x3E].copy_from_slice(&1u16.to_le_bytes());
+        data[0x3E..0x40].copy_from_slice(&1u16.to_le_bytes());
+        let result = ElfFile::parse(&data);
+        assert!(matches!(result, Err(ElfError::Truncated)));
+    }
+
+    #[test]
+    fn directories() {
+        let report = parse_elf("tests/elf/base", "tests/elf/head").unwrap();
+        assert!(report.is_valid());
+        let mut boards = report
+            .boards
+            .iter()
+            .map(|b| (b.board.as_str(), b.sketches.len()))
+            .collect::<Vec<_>>();
+        boards.sort();
+        assert_eq!(
+            boards,
+            [
+                ("arduino:avr:uno", 3),
+                ("arduino:samd:mkrzero", 1),
+                ("esp32:esp32:esp32", 1),
+                ("riscv:generic:rv64", 1)
+            ]
+        );
+
+        // the head build of Button has no base build
+        let button = report
+            .boards
+            .iter()
+            .flat_map(|b| &b.sketches)
+            .find(|s| s.name == "Button")
+            .unwrap();
+        let size = button.sizes[0].get_size();
+        assert!(matches!(size.current.absolute, SizeValue::Known(_)));
+        assert_eq!(size.get_delta().absolute, SizeValue::NotApplicable);
+        assert!(button.breakdown.is_none());
+    }
+
+    #[test]
+    fn single_files() {
+        let report = parse_elf(
+            "tests/elf/base/arduino.avr.uno/Fade.ino.elf",
+            "tests/elf/head/arduino.avr.uno/Fade.ino.elf",
+        )
+        .unwrap();
+        assert_eq!(report.boards[0].board, "arduino:avr:uno");
+        let sketch = &report.boards[0].sketches[0];
+        assert_eq!(sketch.name, "Fade");
+        let SketchSizeKind::Flash { size } = &sketch.sizes[0] else {
+            unreachable!()
+        };
+        let delta = size.get_delta();
+        assert_eq!(delta.absolute, SizeValue::Known(-144));
+        assert_eq!(delta.relative, Some(SizeValue::NotApplicable));
+    }
+}
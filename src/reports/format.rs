@@ -0,0 +1,242 @@
+//! A module to detect the JSON format of a report produced by
+//! (a particular version of) [arduino/compile-sketches].
+//!
+//! [arduino/compile-sketches]: https://github.com/arduino/compile-sketches
+use super::structs::{Board, BoardSize, Report, ReportOld, SizeValue, SketchSizeKind};
+use crate::JsonError;
+use serde_json::Value;
+
+/// The known JSON formats of reports produced by `arduino/compile-sketches`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// The current format: a list of `boards`, each with the board's maximum sizes
+    /// (in [`Board::sizes`]).
+    Current,
+
+    /// A list of `boards` without the board's sizes.
+    ///
+    /// The boards' maximum sizes are only reported per sketch
+    /// (in [`SketchSize::maximum`](super::structs::SketchSize::maximum)).
+    SketchMaximum,
+
+    /// A single `board` at the root of the report.
+    ///
+    /// The board's sizes only describe the range of changes of all its sketches
+    /// (see [`BoardDeltaSize`](super::structs::BoardDeltaSize)).
+    SingleBoard,
+}
+
+impl ReportFormat {
+    /// Detect the format of the given JSON `value`.
+    ///
+    /// Returns [`None`] if the `value` is not in any known format.
+    pub fn detect(value: &Value) -> Option<Self> {
+        let report = value.as_object()?;
+        if let Some(boards) = report.get("boards").and_then(Value::as_array) {
+            let has_sizes = boards
+                .iter()
+                .all(|b| b.get("sizes").is_some_and(|s| !s.is_null()));
+            return Some(if has_sizes && !boards.is_empty() {
+                Self::Current
+            } else {
+                Self::SketchMaximum
+            });
+        }
+        report
+            .get("board")
+            .is_some_and(Value::is_string)
+            .then_some(Self::SingleBoard)
+    }
+
+    /// Deserialize the given JSON `value` (in this format) into a [`Report`].
+    ///
+    /// The boards' maximum sizes are taken from the sketches' sizes
+    /// if the board does not report them.
+    pub fn convert(&self, value: Value) -> Result<Report, serde_json::Error> {
+        let mut report = match self {
+            Self::Current => return serde_json::from_value::<Report>(value),
+            Self::SketchMaximum => serde_json::from_value::<Report>(value)?,
+            Self::SingleBoard => serde_json::from_value::<ReportOld>(value)?.into(),
+        };
+        for board in &mut report.boards {
+            lift_sketch_maximums(board);
+        }
+        Ok(report)
+    }
+}
+
+/// Copy the maximum sizes from the `board`'s sketches into the [`Board::sizes`]
+/// (if the board does not report them).
+///
+/// Only known sizes (not "N/A") are copied.
+fn lift_sketch_maximums(board: &mut Board) {
+    let mut flash = None;
+    let mut ram = None;
+    for size in board.sketches.iter().flat_map(|s| s.sizes.iter()) {
+        let (maximum, found) = match size {
+            SketchSizeKind::Flash { size } => (size.maximum, &mut flash),
+            SketchSizeKind::Ram { size } => (size.maximum, &mut ram),
+        };
+        if found.is_none() && matches!(maximum, Some(SizeValue::Known(_))) {
+            *found = maximum;
+        }
+    }
+    let sizes = board.sizes.get_or_insert_with(Vec::new);
+    for (sketch_maximum, is_flash) in [(flash, true), (ram, false)] {
+        if sketch_maximum.is_none() {
+            continue;
+        }
+        match sizes
+            .iter_mut()
+            .find(|s| matches!(s, BoardSize::Flash { .. }) == is_flash)
+        {
+            Some(BoardSize::Flash { maximum, .. } | BoardSize::Ram { maximum, .. }) => {
+                if maximum.is_none() {
+                    *maximum = sketch_maximum;
+                }
+            }
+            None if is_flash => sizes.push(BoardSize::Flash {
+                maximum: sketch_maximum,
+                delta: None,
+            }),
+            None => sizes.push(BoardSize::Ram {
+                maximum: sketch_maximum,
+                delta: None,
+            }),
+        }
+    }
+    if sizes.is_empty() {
+        board.sizes = None;
+    }
+}
+
+/// Deserialize the given JSON `text` into a [`Report`].
+///
+/// The text's [`ReportFormat`] is detected automatically.
+/// If the format is not recognized, then the error describes why the `text`
+/// is not in the [`ReportFormat::Current`] format.
+pub fn parse_report(text: &str) -> Result<Report, JsonError> {
+    Ok(convert_value(serde_json::from_str::<Value>(text)?)?)
+}
+
+/// Deserialize the given JSON `value` into a [`Report`] (see [`parse_report()`]).
+fn convert_value(value: Value) -> Result<Report, serde_json::Error> {
+    match ReportFormat::detect(&value) {
+        Some(format) => {
+            log::debug!("Detected report format: {format:?}");
+            format.convert(value)
+        }
+        None => serde_json::from_value::<Report>(value),
+    }
+}
+
+/// Deserialize all reports in the given JSON `text`.
+///
+/// The `text` can be a single report, a JSON array of reports,
+/// or a stream of reports (like newline-delimited JSON).
+/// Each report's [`ReportFormat`] is detected automatically (see [`parse_report()`]).
+pub(super) fn parse_reports(text: &str) -> Result<Vec<Report>, JsonError> {
+    let mut reports = vec![];
+    for value in serde_json::Deserializer::from_str(text).into_iter::<Value>() {
+        match value? {
+            Value::Array(values) => {
+                for value in values {
+                    reports.push(convert_value(value)?);
+                }
+            }
+            value => reports.push(convert_value(value)?),
+        }
+    }
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::{ReportFormat, parse_report, parse_reports};
+    use crate::report_structs::{BoardSize, SizeValue};
+    use serde_json::Value;
+    use std::fs;
+
+    fn detect(path: &str) -> Option<ReportFormat> {
+        let value = serde_json::from_str::<Value>(&fs::read_to_string(path).unwrap()).unwrap();
+        ReportFormat::detect(&value)
+    }
+
+    #[test]
+    fn detect_formats() {
+        for (path, expected) in [
+            (
+                "tests/size-deltas-reports-new/arduino-avr-uno.json",
+                ReportFormat::Current,
+            ),
+            (
+                "tests/size-deltas-reports-old/arduino-mbed_portenta-envie_m7.json",
+                ReportFormat::SketchMaximum,
+            ),
+            (
+                "tests/size-deltas-reports-old/arduino-samd-mkrgsm1400.json",
+                ReportFormat::SingleBoard,
+            ),
+        ] {
+            assert_eq!(detect(path), Some(expected), "{path}");
+        }
+        assert_eq!(ReportFormat::detect(&Value::Null), None);
+    }
+
+    #[test]
+    fn single_board() {
+        let text = fs::read_to_string("tests/size-deltas-reports-old/arduino-samd-mkrgsm1400.json")
+            .unwrap();
+        let report = parse_report(&text).unwrap();
+        assert!(report.is_valid());
+        let sizes = report.boards[0].sizes.as_ref().unwrap();
+        let delta = sizes[0].delta().unwrap();
+        assert_eq!(delta.absolute.minimum, SizeValue::Known(-994));
+        assert_eq!(delta.relative, None);
+        assert_eq!(sizes[1].maximum(), None);
+    }
+
+    #[test]
+    fn sketch_maximum() {
+        let text = r#"{"commit_hash": "deadbeef", "commit_url": "", "boards": [{
+            "board": "arduino:avr:uno",
+            "sketches": [{"name": "Foo", "compilation_success": true, "sizes": [
+                {"name": "flash", "maximum": 32256, "current": {"absolute": 444}},
+                {"name": "RAM for global variables", "maximum": "N/A", "current": {"absolute": 9}}
+            ]}]
+        }]}"#;
+        let report = parse_report(text).unwrap();
+        let sizes = report.boards[0].sizes.as_ref().unwrap();
+        assert_eq!(sizes.len(), 1);
+        assert!(matches!(
+            sizes[0],
+            BoardSize::Flash {
+                maximum: Some(SizeValue::Known(32256)),
+                delta: None
+            }
+        ));
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn multiple_reports() {
+        let report = fs::read_to_string("tests/size-deltas-reports-new/arduino-avr-uno.json")
+            .unwrap()
+            .replace('\n', "");
+        let old = fs::read_to_string("tests/size-deltas-reports-old/arduino-samd-mkrgsm1400.json")
+            .unwrap()
+            .replace('\n', "");
+        let ndjson = format!("{report}\n{old}\n");
+        let reports = parse_reports(&ndjson).unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[1].boards[0].board, "arduino:samd:mkrgsm1400");
+
+        let array = format!("[{report}, {old}]");
+        assert_eq!(parse_reports(&array).unwrap(), reports);
+        assert_eq!(parse_reports(&report).unwrap().len(), 1);
+        assert!(parse_reports("").unwrap().is_empty());
+        assert!(parse_reports("[{}]").is_err());
+    }
+}
@@ -0,0 +1,293 @@
+//! A module to parse the linker map files generated by GNU ld (`-Wl,-Map=<file>`).
+//!
+//! Only the "Linker script and memory map" part is parsed. Each input section lists
+//! the object file it was taken from, which allows attributing memory usage to
+//! the object files linked into a compiled sketch:
+//!
+//! ```text
+//! .text           0x00000000      0x3a4
+//!  .text          0x000000b0       0x94 /tmp/arduino/sketches/5F3A/sketch/Blink.ino.cpp.o
+//!  .text.loop
+//!                 0x00000144       0x28 /tmp/arduino/sketches/5F3A/sketch/Blink.ino.cpp.o
+//!                 0x00000144                loop
+//! ```
+use super::structs::LibrarySize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The line that starts the memory map in a linker map file.
+const MEMORY_MAP_START: &str = "Linker script and memory map";
+
+/// An input section (taken from an object file) in a linker map file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct InputSection {
+    /// The name of the output section that contains this input section (for example, `.text`).
+    pub output: String,
+
+    /// The path to the object file (or `archive.a(member.o)`) that this section was taken from.
+    pub object: String,
+
+    /// The section's size in bytes.
+    pub size: u64,
+}
+
+/// Parse a hexadecimal number like `0x000000b0`.
+fn parse_hex(value: &str) -> Option<u64> {
+    u64::from_str_radix(value.strip_prefix("0x")?, 16).ok()
+}
+
+/// Parse the `<address> <size> <object>` fields of an input section.
+fn parse_fields(fields: &[&str]) -> Option<(u64, String)> {
+    let [address, size, object @ ..] = fields else {
+        return None;
+    };
+    parse_hex(address)?;
+    if object.is_empty() {
+        return None;
+    }
+    Some((parse_hex(size)?, object.join(" ")))
+}
+
+/// Parse the input sections in the given linker map `text`.
+///
+/// Input section names that are too long are printed on their own line
+/// (with the address, size and object file on the next line); both layouts are supported.
+pub(super) fn parse_linker_map(text: &str) -> Vec<InputSection> {
+    let mut sections = vec![];
+    let mut output: Option<&str> = None;
+    let mut wrapped = false;
+    let lines = text
+        .lines()
+        .skip_while(|line| !line.starts_with(MEMORY_MAP_START))
+        .take_while(|line| !line.starts_with("OUTPUT("));
+    for line in lines {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        let Some(first) = fields.first() else {
+            continue;
+        };
+        let continued = std::mem::take(&mut wrapped);
+        if !line.starts_with(' ') {
+            // an output section (or a linker script command like `LOAD`)
+            output = first.starts_with('.').then_some(*first);
+            continue;
+        }
+        let Some(output) = output else {
+            continue;
+        };
+        let is_input_section = line.len() > 1 && !line[1..].starts_with(' ');
+        let fields = if is_input_section {
+            // patterns from the linker script (like ` *(.text*)`) and padding are not sections
+            if first.starts_with('*') {
+                continue;
+            }
+            if fields.len() == 1 {
+                wrapped = true;
+                continue;
+            }
+            &fields[1..]
+        } else if continued {
+            &fields[..]
+        } else {
+            // a symbol or an assignment
+            continue;
+        };
+        if let Some((size, object)) = parse_fields(fields) {
+            sections.push(InputSection {
+                output: output.to_string(),
+                object,
+                size,
+            });
+        }
+    }
+    sections
+}
+
+/// Get a short name of an object file that does not depend on the build's (temporary) folder.
+///
+/// Objects from an archive keep the archive's name (for example, `core.a(wiring.c.o)`).
+pub(super) fn object_name(object: &str) -> String {
+    let file_name = |path: &str| {
+        path.trim_end_matches(['/', '\\'])
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or(path)
+            .to_string()
+    };
+    match object.strip_suffix(')').and_then(|o| o.split_once('(')) {
+        Some((archive, member)) => format!("{}({member})", file_name(archive)),
+        None => file_name(object),
+    }
+}
+
+/// Get the name of the library that an object file belongs to.
+///
+/// - Objects in an archive belong to that archive (for example, `libgcc.a`),
+///   except the board's core archive (`core.a`) which is named `core`.
+/// - Objects compiled by arduino-cli are attributed by their folder in the build path:
+///   `sketch`, `core`, or the library's name (from `libraries/<name>/`).
+/// - Any other object (like the toolchain's startup code) is named after its file.
+pub(super) fn library_name(object: &str) -> String {
+    let object = object.replace('\\', "/");
+    if let Some((archive, _)) = object.strip_suffix(')').and_then(|o| o.split_once('(')) {
+        let archive = object_name(archive);
+        return if archive == "core.a" {
+            "core".to_string()
+        } else {
+            archive
+        };
+    }
+    let mut parent: Option<&str> = None;
+    for folder in object.split('/').rev().skip(1) {
+        match (folder, parent) {
+            ("sketch" | "core", _) => return folder.to_string(),
+            ("libraries", Some(library)) => return library.to_string(),
+            _ => parent = Some(folder),
+        }
+    }
+    object_name(&object)
+}
+
+/// Sum the sizes of the input `sections` per group (named by the given `key` function).
+///
+/// Only sections in one of the given `outputs` sections are counted.
+fn group_sizes(
+    sections: &[InputSection],
+    outputs: &[&str],
+    key: impl Fn(&str) -> String,
+) -> BTreeMap<String, u64> {
+    let mut sizes = BTreeMap::new();
+    for section in sections {
+        if outputs.contains(&section.output.as_str()) {
+            *sizes.entry(key(&section.object)).or_default() += section.size;
+        }
+    }
+    sizes
+}
+
+/// Sum the sizes of the input `sections` per object file (see [`object_name()`]).
+///
+/// Only sections in one of the given `outputs` sections are counted.
+pub(super) fn object_sizes(sections: &[InputSection], outputs: &[&str]) -> BTreeMap<String, u64> {
+    group_sizes(sections, outputs, object_name)
+}
+
+/// Compare the flash and RAM usage of each library (see [`library_name()`])
+/// in the input sections of a `base` and `head` build.
+///
+/// The sections in the `flash` output sections count towards the flash memory usage,
+/// and the sections in the `ram` output sections count towards the RAM usage.
+pub(super) fn compare_libraries(
+    base: &[InputSection],
+    head: &[InputSection],
+    flash: &[&str],
+    ram: &[&str],
+) -> Vec<LibrarySize> {
+    let sizes = [
+        group_sizes(base, flash, library_name),
+        group_sizes(head, flash, library_name),
+        group_sizes(base, ram, library_name),
+        group_sizes(head, ram, library_name),
+    ];
+    let names = sizes.iter().flat_map(|s| s.keys()).collect::<BTreeSet<_>>();
+    names
+        .into_iter()
+        .map(|name| {
+            let [previous_flash, current_flash, previous_ram, current_ram] = sizes
+                .each_ref()
+                .map(|s| s.get(name).copied().unwrap_or_default());
+            LibrarySize {
+                name: name.clone(),
+                previous_flash,
+                current_flash,
+                previous_ram,
+                current_ram,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::{compare_libraries, library_name, object_name, object_sizes, parse_linker_map};
+
+    #[test]
+    fn input_sections() {
+        let text = std::fs::read_to_string("tests/elf/head/arduino.avr.uno/Blink.ino.map").unwrap();
+        let sections = parse_linker_map(&text);
+        let sizes = object_sizes(&sections, &[".text", ".data", ".bss"]);
+        assert_eq!(sizes["Blink.ino.cpp.o"], 0x14 + 0x48);
+        assert_eq!(sizes["core.a(wiring.c.o)"], 0x172 + 0x7);
+        assert_eq!(sizes["Wire.cpp.o"], 0x30 + 0x2);
+        // discarded sections are not included
+        assert_eq!(sizes.values().sum::<u64>(), 956 + 9);
+    }
+
+    #[test]
+    fn object_names() {
+        for (object, expected) in [
+            (
+                "/tmp/arduino/sketches/5F3A/sketch/Blink.ino.cpp.o",
+                "Blink.ino.cpp.o",
+            ),
+            (
+                "/tmp/arduino/sketches/5F3A/core/core.a(wiring.c.o)",
+                "core.a(wiring.c.o)",
+            ),
+            (
+                "c:/arduino/avr-gcc/lib/gcc/avr/7.3.0/avr5\\libgcc.a(_udivmodsi4.o)",
+                "libgcc.a(_udivmodsi4.o)",
+            ),
+        ] {
+            assert_eq!(object_name(object), expected);
+        }
+    }
+
+    #[test]
+    fn library_names() {
+        for (object, expected) in [
+            (
+                "/tmp/arduino/sketches/5F3A/sketch/Blink.ino.cpp.o",
+                "sketch",
+            ),
+            ("/tmp/arduino/sketches/5F3A/core/core.a(wiring.c.o)", "core"),
+            ("/tmp/arduino/sketches/5F3A/core/wiring_pulse.S.o", "core"),
+            (
+                "/tmp/arduino/sketches/5F3A/libraries/Wire/utility/twi.c.o",
+                "Wire",
+            ),
+            (
+                "C:\\Temp\\arduino\\sketches\\5F3A\\libraries\\SPI\\SPI.cpp.o",
+                "SPI",
+            ),
+            (
+                "/opt/avr/lib/gcc/avr/7.3.0/avr5/libgcc.a(_udivmodsi4.o)",
+                "libgcc.a",
+            ),
+            ("/opt/avr/lib/avr5/crtatmega328p.o", "crtatmega328p.o"),
+        ] {
+            assert_eq!(library_name(object), expected);
+        }
+    }
+
+    #[test]
+    fn libraries() {
+        let [base, head] = ["base", "head"].map(|side| {
+            let path = format!("tests/elf/{side}/arduino.avr.uno/Blink.ino.map");
+            parse_linker_map(&std::fs::read_to_string(path).unwrap())
+        });
+        let libraries = compare_libraries(&base, &head, &[".text", ".data"], &[".data", ".bss"]);
+        let names = libraries
+            .iter()
+            .map(|l| l.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["Wire", "core", "crtatmega328p.o", "libgcc.a", "sketch"]
+        );
+        let wire = &libraries[0];
+        assert_eq!((wire.current_flash, wire.current_ram), (0x30, 0x2));
+        assert_eq!(libraries[4].flash_delta(), 32);
+        assert_eq!(libraries[4].ram_delta(), 0);
+    }
+}
@@ -1,61 +1,78 @@
 //! A module for API related to parsing of JSON data from CI artifacts.
-//! Additionally, there's a convenient [`parse_json()`] function for parsing of older
+//! Additionally, there's a convenient [`parse_report()`] function for parsing of older
 //! JSON formats produced by the [arduino/compile-sketches] action.
 //!
 //! [arduino/compile-sketches]: https://github.com/arduino/compile-sketches
 use crate::{CommentAssemblyError, JsonError};
-use std::{fs, path::Path};
+use std::{fs, io::Read, path::Path};
+mod arduino_cli;
+mod builds;
+pub(crate) mod discovery;
+mod elf;
+mod format;
+mod linker_map;
+mod platformio;
 pub mod structs;
-use structs::{Report, ReportOld};
+pub use arduino_cli::parse_arduino_cli;
+pub use discovery::{DEFAULT_INCLUDE, DiscoveryOptions};
+pub use elf::parse_elf;
+pub use format::{ReportFormat, parse_report};
+pub use platformio::parse_platformio;
+use structs::Report;
 
 /// Deserialize a JSON file at the given `path` into a [`Report`].
 ///
-/// This will automatically try to parsing old JSON formats when
-/// parsing the newer format fails syntactically.
+/// This will automatically detect (and convert) older JSON formats
+/// (see [`ReportFormat`]).
 pub(crate) fn parse_json<P: AsRef<Path>>(path: P) -> Result<Report, JsonError> {
-    let asset = fs::read_to_string(path)?;
-    match serde_json::from_str::<Report>(&asset) {
-        Ok(report) => Ok(report),
-        Err(e) => {
-            if e.is_data() {
-                // if parsing the new format fails (for typing reasons),
-                // then try the old format and convert it.
-                match serde_json::from_str::<ReportOld>(&asset) {
-                    Ok(report) => Ok(report.into()),
-                    Err(e_old) => {
-                        eprintln!("Parsing old format failed: {e_old}");
-                        Err(JsonError::Serde(e))
-                    }
-                }
-            } else {
-                Err(JsonError::Serde(e))
-            }
+    parse_report(&fs::read_to_string(path)?)
+}
+
+/// Parse the reports read from the given `reader` (for example, stdin).
+///
+/// The data can be a single report, a JSON array of reports,
+/// or a stream of reports (like newline-delimited JSON).
+/// Like [`parse_artifacts()`], reports without sufficient information are skipped.
+pub fn parse_reader<R: Read>(mut reader: R) -> Result<Vec<Report>, JsonError> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let mut reports = format::parse_reports(&text)?;
+    reports.retain(|report| {
+        let is_valid = report.is_valid();
+        if !is_valid {
+            log::warn!("Skipping a report that does not contain sufficient information.");
         }
-    }
+        is_valid
+    });
+    Ok(reports)
 }
 
 /// Recursively scans the given `sketches_path` and parses any existing JSON files as
 /// sketch report artifacts.
+///
+/// This is a shortcut for [`parse_artifacts_with()`] using the default [`DiscoveryOptions`].
 pub fn parse_artifacts<P: AsRef<Path>>(
     sketches_path: P,
+) -> Result<Vec<Report>, CommentAssemblyError> {
+    parse_artifacts_with(sketches_path, &DiscoveryOptions::default())
+}
+
+/// Scans the given `sketches_path` for the files that satisfy the given `options`
+/// and parses them as sketch report artifacts.
+///
+/// The reports are returned in order of their files' paths.
+/// Reports without sufficient information are skipped (see [`Report::is_valid()`]).
+pub fn parse_artifacts_with<P: AsRef<Path>>(
+    sketches_path: P,
+    options: &DiscoveryOptions,
 ) -> Result<Vec<Report>, CommentAssemblyError> {
     let mut reports = vec![];
-    for entry in fs::read_dir(&sketches_path)? {
-        let path = entry?.path();
-        if path.is_dir() {
-            reports.extend(parse_artifacts(path)?);
-        } else if path
-            .extension()
-            .is_some_and(|ext| ext.to_string_lossy() == "json")
-        {
-            let report = parse_json(&path)?;
-            if report.is_valid() {
-                reports.push(report);
-            } else {
-                log::warn!("Skipping {path:?} since it does not contain sufficient information.");
-            }
+    for path in discovery::discover(sketches_path.as_ref(), options)? {
+        let report = parse_json(&path)?;
+        if report.is_valid() {
+            reports.push(report);
         } else {
-            log::debug!("Ignoring non-JSON file: {}", path.to_string_lossy());
+            log::warn!("Skipping {path:?} since it does not contain sufficient information.");
         }
     }
     Ok(reports)
@@ -67,7 +84,7 @@ mod test {
 
     use std::io::Write;
 
-    use super::{JsonError, parse_json};
+    use super::{JsonError, parse_json, parse_reader};
     use tempfile::NamedTempFile;
 
     /// Test parsing of JSON report in newer format
@@ -86,16 +103,17 @@ mod test {
         }
     }
 
-    /// Test parsing of JSON report in newer format
+    /// Test parsing of JSON report in older formats
     #[test]
     fn parse_old() {
-        for entry in std::fs::read_dir("tests/size-deltas-reports-old").unwrap() {
-            let path = entry.unwrap().path();
-            println!("Parsing {path:?}");
-            let report = parse_json(path).unwrap();
-            assert!(!report.boards.is_empty());
-            assert!(!report.is_valid());
-        }
+        let report = parse_json("tests/size-deltas-reports-old/arduino-samd-mkrgsm1400.json");
+        assert!(report.unwrap().is_valid());
+        // all sizes are "N/A" and the board's maximum sizes are unknown
+        let report =
+            parse_json("tests/size-deltas-reports-old/arduino-mbed_portenta-envie_m7.json");
+        let report = report.unwrap();
+        assert!(!report.boards.is_empty());
+        assert!(!report.is_valid());
     }
 
     #[test]
@@ -118,4 +136,21 @@ mod test {
         let result = parse_json(&bad_asset);
         assert!(result.is_err_and(|e| matches!(e, JsonError::Serde(_))));
     }
+
+    #[test]
+    fn reader() {
+        let mut text = vec![];
+        for name in ["arduino-avr-uno", "arduino-avr-nano"] {
+            let path = format!("tests/size-deltas-reports-new/{name}.json");
+            text.extend(std::fs::read(path).unwrap());
+        }
+        let old = "tests/size-deltas-reports-old/arduino-mbed_portenta-envie_m7.json";
+        text.extend(std::fs::read(old).unwrap());
+        let reports = parse_reader(text.as_slice()).unwrap();
+        // the old report is skipped
+        assert_eq!(reports.len(), 2);
+
+        let result = parse_reader("{not json".as_bytes());
+        assert!(result.is_err_and(|e| matches!(e, JsonError::Serde(_))));
+    }
 }
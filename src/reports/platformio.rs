@@ -0,0 +1,209 @@
+//! A module to convert the output of PlatformIO builds (`pio run`) into a [`Report`].
+//!
+//! PlatformIO prints a summary of the memory usage after building each environment:
+//!
+//! ```text
+//! Processing esp32dev (platform: espressif32; board: esp32dev; framework: arduino)
+//! ...
+//! RAM:   [=         ]   6.5% (used 21312 bytes from 327680 bytes)
+//! Flash: [==        ]  20.4% (used 267193 bytes from 1310720 bytes)
+//! ```
+//!
+//! Like the output of arduino-cli, a log of a "base" build (before the changes) is paired
+//! with a log of a "head" build (after the changes) of the same project.
+//! The environment's name is used as the board.
+//!
+//! The JSON output of `pio run -t idedata` is not supported because it does not describe
+//! the memory usage.
+use super::{
+    builds::{MemoryUsage, add_sketch, compare, pair_files},
+    structs::Report,
+};
+use crate::CommentAssemblyError;
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// The environment name used if a log does not specify which environment was built.
+const DEFAULT_ENV: &str = "default";
+
+/// The memory usage of a single PlatformIO environment.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct EnvironmentSize {
+    /// Was the environment successfully built?
+    success: bool,
+
+    /// The flash memory usage.
+    flash: Option<MemoryUsage>,
+
+    /// The RAM usage.
+    ram: Option<MemoryUsage>,
+}
+
+/// Parse a memory usage line like `(used 21312 bytes from 327680 bytes)`.
+fn parse_usage(line: &str) -> Option<MemoryUsage> {
+    let usage = line.split_once("(used ")?.1;
+    let (used, rest) = usage.split_once(" bytes from ")?;
+    let maximum = rest.split_once(" bytes")?.0;
+    Some(MemoryUsage {
+        used: used.trim().parse().ok()?,
+        maximum: maximum.trim().parse().ok()?,
+    })
+}
+
+/// Parse the memory usage of each environment in the output of `pio run`.
+///
+/// An environment is considered successfully built if its flash usage is reported
+/// and it is not marked as `[FAILED]`.
+fn parse_log(log: &str) -> BTreeMap<String, EnvironmentSize> {
+    let mut envs = BTreeMap::new();
+    let mut current = DEFAULT_ENV.to_string();
+    for line in log.lines().map(str::trim) {
+        if let Some(env) = line.strip_prefix("Processing ") {
+            current = env
+                .split_whitespace()
+                .next()
+                .unwrap_or(DEFAULT_ENV)
+                .to_string();
+            envs.entry(current.clone())
+                .or_insert_with(EnvironmentSize::default);
+        } else if let Some(usage) = line.strip_prefix("RAM:") {
+            let env = envs
+                .entry(current.clone())
+                .or_insert_with(EnvironmentSize::default);
+            env.ram = parse_usage(usage);
+        } else if let Some(usage) = line.strip_prefix("Flash:") {
+            let env = envs
+                .entry(current.clone())
+                .or_insert_with(EnvironmentSize::default);
+            env.flash = parse_usage(usage);
+            env.success = env.flash.is_some();
+        } else if line.contains("[FAILED]")
+            && let Some(env) = envs.get_mut(&current)
+        {
+            env.success = false;
+        }
+    }
+    envs
+}
+
+/// Parse the output of PlatformIO builds (`pio run`) into a [`Report`].
+///
+/// The `base` and `head` paths can both be single log files or both be directories
+/// (scanned recursively for `.log` and `.txt` files). Files in the `head` directory are paired with
+/// the file at the same relative path in the `base` directory.
+/// Head builds without a base build (like a new sketch) are reported without previous sizes.
+///
+/// Each environment in a log is reported as a board, and the log's path
+/// (relative to the `head` directory, without its extension) is used as the sketch's name.
+/// The [`Report::commit_hash`] and [`Report::commit_url`] are left empty
+/// because PlatformIO does not know about them.
+pub fn parse_platformio<P: AsRef<Path>>(base: P, head: P) -> Result<Report, CommentAssemblyError> {
+    let head_root = head.as_ref();
+    let pairs = pair_files(base.as_ref(), head_root, |path| {
+        path.extension()
+            .is_some_and(|ext| matches!(ext.to_string_lossy().as_ref(), "log" | "txt"))
+    })?;
+    let mut report = Report::default();
+    for (base_path, head_path) in pairs {
+        let base_envs = match base_path {
+            Some(path) => parse_log(&fs::read_to_string(path)?),
+            None => BTreeMap::new(),
+        };
+        let head_envs = parse_log(&fs::read_to_string(&head_path)?);
+        let name = head_path
+            .strip_prefix(head_root)
+            .ok()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new(head_path.file_name().unwrap_or_default()))
+            .with_extension("");
+        let name = name.to_string_lossy().replace('\\', "/");
+        for (env, head) in head_envs {
+            let base = base_envs.get(&env).copied().unwrap_or_default();
+            let (sketch, sizes) = compare(
+                name.clone(),
+                head.success,
+                [base.flash, head.flash],
+                [base.ram, head.ram],
+            );
+            add_sketch(&mut report, &env, sketch, sizes);
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::{parse_log, parse_platformio};
+    use crate::report_structs::{SizeValue, SketchSizeKind};
+    use std::fs;
+
+    #[test]
+    fn log_environments() {
+        let log = std::fs::read_to_string("tests/platformio/head/blink.log").unwrap();
+        let envs = parse_log(&log);
+        assert_eq!(envs.keys().collect::<Vec<_>>(), ["esp32dev", "uno"]);
+        let esp32 = &envs["esp32dev"];
+        assert!(esp32.success);
+        assert_eq!(esp32.flash.unwrap().used, 267_321);
+        assert_eq!(esp32.ram.unwrap().maximum, 327_680);
+    }
+
+    #[test]
+    fn failed_build() {
+        let log = std::fs::read_to_string("tests/platformio/head/fade.log").unwrap();
+        let env = &parse_log(&log)["uno"];
+        assert!(!env.success);
+        assert_eq!(env.flash, None);
+    }
+
+    #[test]
+    fn directories() {
+        let report = parse_platformio("tests/platformio/base", "tests/platformio/head").unwrap();
+        assert!(report.is_valid());
+        let boards = report
+            .boards
+            .iter()
+            .map(|b| (b.board.as_str(), b.sketches.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(boards, [("esp32dev", 1), ("uno", 4)]);
+        let sketch = &report.boards[0].sketches[0];
+        assert_eq!(sketch.name, "blink");
+        let SketchSizeKind::Flash { size } = &sketch.sizes[0] else {
+            unreachable!()
+        };
+        assert_eq!(size.get_delta().absolute, SizeValue::Known(128));
+
+        // the head builds of button and serial have no base build
+        let names = report.boards[1].sketches.iter().map(|s| s.name.as_str());
+        assert_eq!(
+            names.collect::<Vec<_>>(),
+            ["blink", "button", "fade", "serial"]
+        );
+        let serial = &report.boards[1].sketches[3];
+        assert!(serial.compilation_success);
+        let size = serial.sizes[0].get_size();
+        assert_eq!(size.current.absolute, SizeValue::Known(1476));
+        assert_eq!(size.get_delta().absolute, SizeValue::NotApplicable);
+    }
+
+    #[test]
+    fn ignore_other_files() {
+        let base = tempfile::tempdir().unwrap();
+        let head = tempfile::tempdir().unwrap();
+        for dir in [&base, &head] {
+            fs::copy(
+                "tests/platformio/head/blink.log",
+                dir.path().join("blink.txt"),
+            )
+            .unwrap();
+            // a firmware binary (not valid UTF-8) left in the build folder
+            let build = dir.path().join(".pio/build/uno");
+            fs::create_dir_all(&build).unwrap();
+            fs::write(build.join("firmware.bin"), [0x0C, 0x94, 0xFF, 0xFE]).unwrap();
+        }
+        let report = parse_platformio(base.path(), head.path()).unwrap();
+        assert_eq!(report.boards.len(), 2);
+        assert!(report.boards.iter().all(|b| b.sketches.len() == 1));
+    }
+}
@@ -12,10 +12,12 @@
 //! There doesn't seem to be a documented schema for the JSON data being parsed.'
 //! All python code producing the JSON data is partially typed, so it hard to
 //! discern a proper schema.
+use super::builds::relative;
 use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Visitor};
 
 /// The root structure that describes a report about compilation.
-#[derive(Debug, Deserialize, Default, Serialize)]
+#[derive(Debug, Deserialize, Default, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Report {
     /// The boards targeted when compiling sketches.
     pub boards: Vec<Board>,
@@ -26,6 +28,38 @@ pub struct Report {
 }
 
 impl Report {
+    /// Create a [`Report`] (without any boards) about a compilation
+    /// from the given commit.
+    ///
+    /// ```
+    /// use arduino_report_size_deltas::report_structs::{Board, Report, Sketch, SketchSize};
+    ///
+    /// let report = Report::new("651f05f", "https://github.com/owner/repo/commit/651f05f")
+    ///     .with_board(
+    ///         Board::new("arduino:avr:uno")
+    ///             .with_maximums(32256, 2048)
+    ///             .with_sketch(
+    ///                 Sketch::new("examples/Blink")
+    ///                     .with_flash(SketchSize::new(924, Some(900)))
+    ///                     .with_ram(SketchSize::new(9, Some(9))),
+    ///             ),
+    ///     );
+    /// assert!(report.is_valid());
+    /// ```
+    pub fn new(commit_hash: impl Into<String>, commit_url: impl Into<String>) -> Self {
+        Self {
+            boards: vec![],
+            commit_hash: commit_hash.into(),
+            commit_url: commit_url.into(),
+        }
+    }
+
+    /// Add the given `board` to the [`Report::boards`].
+    pub fn with_board(mut self, board: Board) -> Self {
+        self.boards.push(board);
+        self
+    }
+
     /// Ensure all needed data is present.
     ///
     /// [`parse_artifacts()`][fn@crate::parse_artifacts] supports parsing of
@@ -41,7 +75,7 @@ impl Report {
                 None => return false,
                 Some(sizes) => {
                     for size in sizes {
-                        if !size.has_maximum() {
+                        if !size.is_known() {
                             return false;
                         }
                     }
@@ -54,7 +88,8 @@ impl Report {
 
 /// A intermediate structure used to translate olf JSON formats into the newer format.
 #[derive(Debug, Deserialize)]
-pub(super) struct ReportOld {
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub(crate) struct ReportOld {
     pub board: String,
     pub commit_hash: String,
     pub commit_url: String,
@@ -81,7 +116,8 @@ impl From<ReportOld> for Report {
 /// A data structure to describe the target [`Board::board`] and compilation context.
 ///
 /// Includes it's  ([`Board::sizes`]), and which [`Board::sketches`] were compiled.
-#[derive(Debug, Deserialize, Default, Serialize)]
+#[derive(Debug, Deserialize, Default, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Board {
     /// The board's "Fully Qualified Board Name" (FQBN).
     ///
@@ -95,8 +131,70 @@ pub struct Board {
     pub sizes: Option<Vec<BoardSize>>,
 }
 
+impl Board {
+    /// Create a [`Board`] (without any sketches) with the given FQBN.
+    pub fn new(board: impl Into<String>) -> Self {
+        Self {
+            board: board.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Add the given `sketch` to the [`Board::sketches`].
+    ///
+    /// If the board's maximum sizes are known (see [`Board::with_maximums()`]),
+    /// then the sketch's sizes relative to them are also computed.
+    pub fn with_sketch(mut self, sketch: Sketch) -> Self {
+        self.sketches.push(sketch);
+        self.apply_maximums();
+        self
+    }
+
+    /// Set the board's capacity (in bytes) of `flash` memory and `ram`.
+    ///
+    /// The sizes (relative to these maximums) of the board's sketches are also computed.
+    pub fn with_maximums(mut self, flash: u64, ram: u64) -> Self {
+        self.sizes = Some(vec![
+            BoardSize::Flash {
+                maximum: Some(SizeValue::Known(flash)),
+                delta: None,
+            },
+            BoardSize::Ram {
+                maximum: Some(SizeValue::Known(ram)),
+                delta: None,
+            },
+        ]);
+        self.apply_maximums();
+        self
+    }
+
+    /// Apply the board's maximum sizes to the sizes of all sketches
+    /// that do not specify a [`SketchSize::maximum`].
+    fn apply_maximums(&mut self) {
+        let (mut flash, mut ram) = (None, None);
+        for size in self.sizes.iter().flatten() {
+            match size {
+                BoardSize::Flash { maximum, .. } => flash = *maximum,
+                BoardSize::Ram { maximum, .. } => ram = *maximum,
+            }
+        }
+        for size in self.sketches.iter_mut().flat_map(|s| s.sizes.iter_mut()) {
+            let (maximum, size) = match size {
+                SketchSizeKind::Flash { size } => (flash, size),
+                SketchSizeKind::Ram { size } => (ram, size),
+            };
+            if size.maximum.is_none()
+                && let Some(SizeValue::Known(maximum)) = maximum
+            {
+                *size = std::mem::take(size).with_maximum(maximum);
+            }
+        }
+    }
+}
+
 /// A data structure used to describe a compiled sketch.
-#[derive(Debug, Deserialize, Default, Serialize)]
+#[derive(Debug, Deserialize, Default, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Sketch {
     /// The relative path to the sketch compiled.
     ///
@@ -117,10 +215,189 @@ pub struct Sketch {
     /// This information is only included in the report artifacts when the
     /// `enable-warnings-report` option is enabled for `arduino/compile-sketches`.
     pub warnings: Option<SketchWarnings>,
+
+    /// The changes in size of the sketch's symbols and object files (if known).
+    ///
+    /// This is not included in the artifacts of `arduino/compile-sketches`.
+    /// It is only computed from ELF (and linker map) files (see [`crate::parse_elf()`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub breakdown: Option<SizeBreakdown>,
+}
+
+impl Sketch {
+    /// Create a successfully compiled [`Sketch`] (without any sizes) at the given path.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            compilation_success: true,
+            ..Default::default()
+        }
+    }
+
+    /// Set whether the sketch was successfully compiled.
+    pub fn with_compilation_success(mut self, success: bool) -> Self {
+        self.compilation_success = success;
+        self
+    }
+
+    /// Set the sketch's flash memory usage.
+    ///
+    /// This replaces any flash memory usage that was previously set.
+    pub fn with_flash(mut self, size: SketchSize) -> Self {
+        self.sizes
+            .retain(|s| !matches!(s, SketchSizeKind::Flash { .. }));
+        self.sizes.push(SketchSizeKind::Flash { size });
+        self
+    }
+
+    /// Set the sketch's usage of RAM for global variables.
+    ///
+    /// This replaces any RAM usage that was previously set.
+    pub fn with_ram(mut self, size: SketchSize) -> Self {
+        self.sizes
+            .retain(|s| !matches!(s, SketchSizeKind::Ram { .. }));
+        self.sizes.push(SketchSizeKind::Ram { size });
+        self
+    }
+
+    /// Set the number of compilation warnings before (`previous`) and after (`current`) the changes.
+    pub fn with_warnings(mut self, current: i32, previous: i32) -> Self {
+        self.warnings = Some(SketchWarnings {
+            current: AbsCount { absolute: current },
+            previous: AbsCount { absolute: previous },
+            delta: AbsCount {
+                absolute: current - previous,
+            },
+        });
+        self
+    }
+
+    /// Set the changes in size of the sketch's components.
+    pub fn with_breakdown(mut self, breakdown: SizeBreakdown) -> Self {
+        self.breakdown = Some(breakdown);
+        self
+    }
+
+    /// Get the relative path to the sketch's primary source file.
+    ///
+    /// Arduino requires a sketch's primary `.ino` file to be named after its folder.
+    /// If [`Sketch::name`] already points to a `.ino` file, then it is returned as is.
+    /// The returned path always uses posix-style path separators (`/`).
+    pub fn main_file(&self) -> String {
+        let name = self.name.replace('\\', "/");
+        let name = name.trim_end_matches('/');
+        if name.ends_with(".ino") {
+            return name.to_string();
+        }
+        let stem = name.rsplit('/').next().unwrap_or(name);
+        format!("{name}/{stem}.ino")
+    }
+}
+
+/// The changes in size of a sketch's components (see [`Sketch::breakdown`]).
+///
+/// Only components whose size changed are included, sorted from
+/// the largest increase to the largest decrease.
+#[derive(Debug, Deserialize, Default, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SizeBreakdown {
+    /// The changes in size of each symbol (function or variable).
+    #[serde(default)]
+    pub symbols: Vec<ComponentSize>,
+
+    /// The changes in size of each object file (as listed in a linker map file).
+    #[serde(default)]
+    pub objects: Vec<ComponentSize>,
+
+    /// The flash and RAM usage of each library (as attributed from a linker map file).
+    ///
+    /// Unlike the other components, this includes every library (even if unchanged),
+    /// sorted by name.
+    #[serde(default)]
+    pub libraries: Vec<LibrarySize>,
+}
+
+impl SizeBreakdown {
+    /// Is there no known component?
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty() && self.objects.is_empty() && self.libraries.is_empty()
+    }
+
+    /// Get (at most) the `count` largest increases followed by
+    /// (at most) the `count` largest decreases from the given `components`.
+    pub fn top_changes(components: &[ComponentSize], count: usize) -> Vec<&ComponentSize> {
+        let growers = components.iter().filter(|c| c.delta() > 0).take(count);
+        let mut shrinkers = components
+            .iter()
+            .rev()
+            .filter(|c| c.delta() < 0)
+            .take(count)
+            .collect::<Vec<_>>();
+        shrinkers.reverse();
+        growers.chain(shrinkers).collect()
+    }
+}
+
+/// The size of a single component (symbol or object file) before and after the changes.
+#[derive(Debug, Deserialize, Default, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ComponentSize {
+    /// The component's name.
+    pub name: String,
+
+    /// The size (in bytes) before the changes (`0` if the component was added).
+    pub previous: u64,
+
+    /// The size (in bytes) after the changes (`0` if the component was removed).
+    pub current: u64,
+}
+
+impl ComponentSize {
+    /// The change in size from [`ComponentSize::previous`] to [`ComponentSize::current`].
+    pub fn delta(&self) -> i64 {
+        self.current as i64 - self.previous as i64
+    }
+}
+
+/// The flash and RAM usage of a library before and after the changes
+/// (see [`SizeBreakdown::libraries`]).
+///
+/// The sketch itself and the board's core are also reported as libraries
+/// (named `sketch` and `core` respectively).
+#[derive(Debug, Deserialize, Default, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LibrarySize {
+    /// The library's name (for example, `Wire` or `libgcc.a`).
+    pub name: String,
+
+    /// The library's flash memory usage (in bytes) before the changes.
+    pub previous_flash: u64,
+
+    /// The library's flash memory usage (in bytes) after the changes.
+    pub current_flash: u64,
+
+    /// The library's RAM usage (in bytes) before the changes.
+    pub previous_ram: u64,
+
+    /// The library's RAM usage (in bytes) after the changes.
+    pub current_ram: u64,
+}
+
+impl LibrarySize {
+    /// The change in flash memory usage.
+    pub fn flash_delta(&self) -> i64 {
+        self.current_flash as i64 - self.previous_flash as i64
+    }
+
+    /// The change in RAM usage.
+    pub fn ram_delta(&self) -> i64 {
+        self.current_ram as i64 - self.previous_ram as i64
+    }
 }
 
 /// The number of warnings about a particular sketch's compilation.
-#[derive(Debug, Deserialize, Default, Serialize)]
+#[derive(Debug, Deserialize, Default, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SketchWarnings {
     /// The current number of warnings from latest compilation.
     pub current: AbsCount,
@@ -133,7 +410,8 @@ pub struct SketchWarnings {
 }
 
 /// An absolute count used for the values of [`SketchWarnings`].
-#[derive(Debug, Deserialize, Default, Serialize)]
+#[derive(Debug, Deserialize, Default, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AbsCount {
     /// The absolute 32-bit integer value.
     ///
@@ -144,7 +422,8 @@ pub struct AbsCount {
 /// A data structure to describe a compilation's size.
 ///
 /// Used for [`SketchSizeKind::Ram`] and [`SketchSizeKind::Flash`].
-#[derive(Debug, Deserialize, Default, Serialize)]
+#[derive(Debug, Deserialize, Default, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SketchSize {
     /// The maximum size of something.
     ///
@@ -168,6 +447,41 @@ pub struct SketchSize {
 }
 
 impl SketchSize {
+    /// Create a [`SketchSize`] from the `current` usage (in bytes)
+    /// and the `previous` usage (if there was a previous compilation).
+    ///
+    /// The relative sizes are only known after calling [`SketchSize::with_maximum()`].
+    pub fn new(current: i64, previous: Option<i64>) -> Self {
+        let size = |absolute: Option<i64>| SketchDeltaSize {
+            absolute: absolute.map_or(SizeValue::NotApplicable, SizeValue::Known),
+            relative: None,
+        };
+        Self {
+            maximum: None,
+            current: size(Some(current)),
+            previous: Some(size(previous)),
+            delta: Some(size(previous.map(|p| current - p))),
+        }
+    }
+
+    /// Set the `maximum` size (in bytes) and compute the sizes relative to it
+    /// (in percent, rounded to 2 decimal places).
+    pub fn with_maximum(mut self, maximum: u64) -> Self {
+        self.maximum = Some(SizeValue::Known(maximum));
+        let sizes = [
+            Some(&mut self.current),
+            self.previous.as_mut(),
+            self.delta.as_mut(),
+        ];
+        for size in sizes.into_iter().flatten() {
+            size.relative = Some(match size.absolute {
+                SizeValue::Known(absolute) => relative(absolute, maximum),
+                SizeValue::NotApplicable => SizeValue::NotApplicable,
+            });
+        }
+        self
+    }
+
     /// A convenience function to get [`SketchSize::delta`].
     ///
     /// Falls back to [`SketchSize::current`] when [`SketchSize::delta`] is [`None`].
@@ -177,8 +491,9 @@ impl SketchSize {
 }
 
 /// An enumeration of possible compilation size kinds.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(tag = "name")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum SketchSizeKind {
     /// The compilation size of "Ram for global variables".
     #[serde(rename = "RAM for global variables")]
@@ -237,6 +552,25 @@ pub enum SizeValue<T> {
     Known(T),
 }
 
+/// The schema of a [`SizeValue`] is either the known value or any string
+/// (which is deserialized as [`SizeValue::NotApplicable`]).
+#[cfg(feature = "schema")]
+impl<T: schemars::JsonSchema> schemars::JsonSchema for SizeValue<T> {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        format!("SizeValue_{}", T::schema_name()).into()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "description": "A known value, or any string (typically \"N/A\") if not applicable.",
+            "anyOf": [
+                generator.subschema_for::<T>(),
+                { "type": "string" },
+            ],
+        })
+    }
+}
+
 /// Custom deserializer function
 fn any_str_val_is_not_applicable<'de, D>(deserializer: D) -> Result<(), D::Error>
 where
@@ -272,7 +606,8 @@ where
 }
 
 /// A data structure to describe fields in [`SketchSize`].
-#[derive(Debug, Deserialize, Default, Serialize)]
+#[derive(Debug, Deserialize, Default, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SketchDeltaSize {
     /// The absolute compilation size value.
     ///
@@ -287,35 +622,97 @@ pub struct SketchDeltaSize {
 }
 
 /// An enumeration of a [`Board::sizes`].
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(tag = "name")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum BoardSize {
     /// The maximum size of "RAM for global variables".
     #[serde(rename = "RAM for global variables")]
-    Ram { maximum: Option<SizeValue<u64>> },
+    Ram {
+        maximum: Option<SizeValue<u64>>,
+        /// The range of changes in size of all the board's sketches.
+        ///
+        /// Only present in older JSON formats (see [`BoardDeltaSize`]).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        delta: Option<BoardDeltaSize>,
+    },
     /// The maximum size of flash memory.
     #[serde(rename = "flash")]
-    Flash { maximum: Option<SizeValue<u64>> },
+    Flash {
+        maximum: Option<SizeValue<u64>>,
+        /// The range of changes in size of all the board's sketches.
+        ///
+        /// Only present in older JSON formats (see [`BoardDeltaSize`]).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        delta: Option<BoardDeltaSize>,
+    },
 }
 
 impl Default for BoardSize {
     fn default() -> Self {
         BoardSize::Flash {
             maximum: Default::default(),
+            delta: Default::default(),
         }
     }
 }
 
 impl BoardSize {
     /// A convenience function to ensure the board's maximum sizes are defined.
-    ///
-    /// Primarily used by [`Report::is_valid()`].
     pub fn has_maximum(&self) -> bool {
+        self.maximum().is_some()
+    }
+
+    /// A convenience function to get the board's maximum size (if defined).
+    pub fn maximum(&self) -> Option<SizeValue<u64>> {
+        match self {
+            BoardSize::Ram { maximum, .. } | BoardSize::Flash { maximum, .. } => *maximum,
+        }
+    }
+
+    /// A convenience function to get the range of changes in size (if defined).
+    pub fn delta(&self) -> Option<&BoardDeltaSize> {
         match self {
-            BoardSize::Ram { maximum } => maximum.is_some(),
-            BoardSize::Flash { maximum } => maximum.is_some(),
+            BoardSize::Ram { delta, .. } | BoardSize::Flash { delta, .. } => delta.as_ref(),
         }
     }
+
+    /// Is there enough data to summarize the board's size?
+    ///
+    /// This requires either the board's maximum size or (in older JSON formats)
+    /// the range of changes in size. Primarily used by [`Report::is_valid()`].
+    pub fn is_known(&self) -> bool {
+        self.has_maximum() || self.delta().is_some()
+    }
+}
+
+/// The range of changes in size of all sketches compiled for a board.
+///
+/// Older JSON formats (that describe a single board) include this in the board's sizes:
+///
+/// ```json
+/// {"name": "flash", "delta": {"absolute": {"minimum": -994, "maximum": -994}}}
+/// ```
+#[derive(Debug, Deserialize, Default, Serialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BoardDeltaSize {
+    /// The range of absolute changes in size.
+    pub absolute: SizeRange<i64>,
+
+    /// The range of changes in size relative to the board's maximum size (if known).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relative: Option<SizeRange<f32>>,
+}
+
+/// A range of values used in [`BoardDeltaSize`].
+#[derive(Debug, Deserialize, Default, Serialize, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SizeRange<T> {
+    /// The smallest value.
+    pub minimum: SizeValue<T>,
+
+    /// The largest value.
+    pub maximum: SizeValue<T>,
 }
 
 #[cfg(test)]
@@ -324,7 +721,7 @@ mod test {
 
     use crate::report_structs::{BoardSize, SizeValue};
 
-    use super::{Report, SketchSize, SketchSizeKind};
+    use super::{Board, Report, Sketch, SketchSize, SketchSizeKind};
 
     #[test]
     fn no_boards() {
@@ -348,7 +745,10 @@ mod test {
 
         assert!(matches!(
             BoardSize::default(),
-            BoardSize::Flash { maximum: None }
+            BoardSize::Flash {
+                maximum: None,
+                delta: None
+            }
         ));
     }
 
@@ -368,10 +768,58 @@ mod test {
         }
     }
 
+    #[test]
+    fn sketch_main_file() {
+        for (name, expected) in [
+            ("examples/Foo", "examples/Foo/Foo.ino"),
+            ("examples\\Foo\\", "examples/Foo/Foo.ino"),
+            ("examples/Foo/Foo.ino", "examples/Foo/Foo.ino"),
+            ("Foo", "Foo/Foo.ino"),
+        ] {
+            let sketch = Sketch {
+                name: name.to_string(),
+                ..Default::default()
+            };
+            assert_eq!(sketch.main_file(), expected);
+        }
+    }
+
     #[test]
     fn serialize_not_applicable() {
         let size_value = SizeValue::<u8>::NotApplicable;
         let serialized = serde_json::to_string(&size_value).unwrap();
         assert_eq!(serialized, r#""N/A""#);
     }
+
+    #[test]
+    fn build_report() {
+        let report = Report::new("deadbeef", "").with_board(
+            Board::new("arduino:avr:uno")
+                .with_sketch(
+                    Sketch::new("examples/Blink")
+                        .with_flash(SketchSize::new(1000, None))
+                        .with_flash(SketchSize::new(1032, Some(1000)))
+                        .with_warnings(2, 1),
+                )
+                .with_maximums(32000, 2048),
+        );
+        assert!(report.is_valid());
+        let sketch = &report.boards[0].sketches[0];
+        assert!(sketch.compilation_success);
+        assert_eq!(sketch.sizes.len(), 1);
+        let size = sketch.sizes[0].get_size();
+        assert_eq!(size.maximum, Some(SizeValue::Known(32000)));
+        assert_eq!(size.get_delta().absolute, SizeValue::Known(32));
+        assert_eq!(size.get_delta().relative, Some(SizeValue::Known(0.1)));
+        assert_eq!(sketch.warnings.as_ref().unwrap().delta.absolute, 1);
+        assert_eq!(report.clone(), report);
+    }
+
+    #[test]
+    fn build_new_sketch_size() {
+        let size = SketchSize::new(9, None).with_maximum(2048);
+        assert_eq!(size.get_delta().absolute, SizeValue::NotApplicable);
+        assert_eq!(size.get_delta().relative, Some(SizeValue::NotApplicable));
+        assert_eq!(size.current.relative, Some(SizeValue::Known(0.44)));
+    }
 }
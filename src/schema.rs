@@ -0,0 +1,284 @@
+//! A module to generate (and validate JSON data against) the JSON schemas of
+//! the reports parsed and the summary produced by this crate.
+//!
+//! The schemas are also shipped with this crate in the `schemas/v<SCHEMA_VERSION>` folder.
+use crate::{
+    CommentAssemblyError, DiscoveryOptions, JsonError,
+    reports::{
+        discovery::discover,
+        structs::{Report, ReportOld},
+    },
+    summarize::model::Summary,
+};
+use serde_json::Value;
+use std::{
+    fmt::Display,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+/// The version of the JSON schemas.
+///
+/// This is incremented whenever the schemas change in a way that is not backward compatible.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The base URL used for the `$id` of each schema.
+const SCHEMA_BASE_URL: &str = "https://github.com/2bndy5/arduino-report-size-deltas/schemas";
+
+/// The kinds of JSON data described by a schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaKind {
+    /// A report produced by `arduino/compile-sketches` (see [`Report`]).
+    Report,
+
+    /// A report in the old format produced by `arduino/compile-sketches`
+    /// (describing a single board).
+    ReportOld,
+
+    /// The summary output by the [`JsonRenderer`](crate::JsonRenderer) (see [`Summary`]).
+    Summary,
+}
+
+impl SchemaKind {
+    /// All kinds of schemas.
+    pub const ALL: [SchemaKind; 3] = [Self::Report, Self::ReportOld, Self::Summary];
+
+    /// The name of the file that the schema is shipped in.
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Self::Report => "report.schema.json",
+            Self::ReportOld => "report-old.schema.json",
+            Self::Summary => "summary.schema.json",
+        }
+    }
+
+    /// Generate the JSON schema (identified by the [`SCHEMA_VERSION`]).
+    pub fn schema(&self) -> Value {
+        let schema = match self {
+            Self::Report => schemars::schema_for!(Report),
+            Self::ReportOld => schemars::schema_for!(ReportOld),
+            Self::Summary => schemars::schema_for!(Summary),
+        };
+        let mut schema = schema.to_value();
+        if let Some(schema) = schema.as_object_mut() {
+            let id = format!("{SCHEMA_BASE_URL}/v{SCHEMA_VERSION}/{}", self.file_name());
+            schema.insert("$id".to_string(), Value::String(id));
+        }
+        schema
+    }
+
+    /// Get the compiled validator of this kind of schema.
+    ///
+    /// The schema is only compiled once (on first use). If the schema itself is invalid,
+    /// then the error message is returned instead.
+    fn validator(&self) -> &'static Result<jsonschema::Validator, String> {
+        static VALIDATORS: [OnceLock<Result<jsonschema::Validator, String>>; 3] =
+            [const { OnceLock::new() }; 3];
+        let index = match self {
+            Self::Report => 0,
+            Self::ReportOld => 1,
+            Self::Summary => 2,
+        };
+        VALIDATORS[index]
+            .get_or_init(|| jsonschema::validator_for(&self.schema()).map_err(|e| e.to_string()))
+    }
+
+    /// Validate the given JSON `instance` against this kind of schema.
+    ///
+    /// Returns all the problems found (or nothing if the `instance` is valid).
+    pub fn validate(&self, instance: &Value) -> Vec<ValidationIssue> {
+        match self.validator() {
+            Ok(validator) => validator
+                .iter_errors(instance)
+                .map(|e| ValidationIssue {
+                    path: e.instance_path().to_string(),
+                    message: e.to_string(),
+                })
+                .collect(),
+            Err(e) => vec![ValidationIssue {
+                path: String::new(),
+                message: format!("The schema itself is invalid: {e}"),
+            }],
+        }
+    }
+}
+
+/// A problem found when validating JSON data against a schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// The JSON pointer to the invalid value (for example, `/boards/0/sketches`).
+    ///
+    /// This is empty if the problem is with the root value.
+    pub path: String,
+
+    /// A description of the problem (for example, `"commit_hash" is a required property`).
+    pub message: String,
+}
+
+impl Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        write!(f, "{path}: {}", self.message)
+    }
+}
+
+/// Validate the given JSON `text` as a report produced by `arduino/compile-sketches`.
+///
+/// Like [`parse_artifacts()`](crate::parse_artifacts), the old report format is also accepted.
+/// If the `text` is neither format, then the problems found with the current format
+/// are returned. Returns an error if the `text` is not valid JSON.
+pub fn validate_report(text: &str) -> Result<Vec<ValidationIssue>, JsonError> {
+    Ok(validate_value(&serde_json::from_str::<Value>(text)?))
+}
+
+/// Validate the given JSON `instance` as a report (see [`validate_report()`]).
+fn validate_value(instance: &Value) -> Vec<ValidationIssue> {
+    let issues = SchemaKind::Report.validate(instance);
+    if !issues.is_empty() && SchemaKind::ReportOld.validate(instance).is_empty() {
+        return vec![];
+    }
+    issues
+}
+
+/// Validate the reports read from the given `reader` (for example, stdin).
+///
+/// Like [`parse_reader()`](crate::parse_reader), the data can be a single report,
+/// a JSON array of reports, or a stream of reports (like newline-delimited JSON).
+/// Returns the problems found in each report (in the order they were read).
+pub fn validate_reader<R: Read>(mut reader: R) -> Result<Vec<Vec<ValidationIssue>>, JsonError> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let mut results = vec![];
+    for value in serde_json::Deserializer::from_str(&text).into_iter::<Value>() {
+        match value? {
+            Value::Array(values) => results.extend(values.iter().map(validate_value)),
+            value => results.push(validate_value(&value)),
+        }
+    }
+    Ok(results)
+}
+
+/// Read and validate the report in the JSON file at the given `path`
+/// (see [`validate_report()`]).
+pub fn validate_file<P: AsRef<Path>>(path: P) -> Result<Vec<ValidationIssue>, JsonError> {
+    validate_report(&fs::read_to_string(path)?)
+}
+
+/// Validate the report(s) at the given `path` (see [`validate_file()`]).
+///
+/// If the `path` is a directory, then it is scanned for the files that satisfy the given
+/// `options` (like [`parse_artifacts_with()`](crate::parse_artifacts_with)).
+/// Returns the problems found in each file (sorted by the file's path).
+pub fn validate_artifacts<P: AsRef<Path>>(
+    path: P,
+    options: &DiscoveryOptions,
+) -> Result<Vec<(PathBuf, Vec<ValidationIssue>)>, CommentAssemblyError> {
+    let path = path.as_ref();
+    if !path.is_dir() {
+        return Ok(vec![(path.to_path_buf(), validate_file(path)?)]);
+    }
+    let mut results = vec![];
+    for file in discover(path, options)? {
+        let issues = validate_file(&file)?;
+        results.push((file, issues));
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::{
+        SCHEMA_VERSION, SchemaKind, validate_artifacts, validate_file, validate_reader,
+        validate_report,
+    };
+    use crate::{
+        DiscoveryOptions, JsonError, JsonRenderer, Renderer, reports::parse_artifacts,
+        summary_structs::Summary,
+    };
+    use std::{env, fs};
+
+    /// The shipped schemas must match the generated schemas.
+    ///
+    /// Set the `UPDATE_SCHEMAS=1` environment variable to regenerate the shipped schemas.
+    #[test]
+    fn shipped_schemas() {
+        let dir = format!("schemas/v{SCHEMA_VERSION}");
+        let update = env::var("UPDATE_SCHEMAS").is_ok_and(|v| v == "1");
+        if update {
+            fs::create_dir_all(&dir).unwrap();
+        }
+        for kind in SchemaKind::ALL {
+            let path = format!("{dir}/{}", kind.file_name());
+            let schema = serde_json::to_string_pretty(&kind.schema()).unwrap() + "\n";
+            if update {
+                fs::write(&path, schema).unwrap();
+            } else {
+                assert_eq!(
+                    fs::read_to_string(&path).unwrap(),
+                    schema,
+                    "{path} is outdated; run the tests with UPDATE_SCHEMAS=1 to regenerate it"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn valid_reports() {
+        for dir in ["tests/test_assets", "tests/size-deltas-reports-new"] {
+            for (path, issues) in validate_artifacts(dir, &DiscoveryOptions::default()).unwrap() {
+                assert_eq!(issues, vec![], "{path:?}");
+            }
+        }
+        // the old format
+        let old = "tests/size-deltas-reports-old/arduino-samd-mkrgsm1400.json";
+        assert!(validate_file(old).unwrap().is_empty());
+    }
+
+    #[test]
+    fn valid_summary() {
+        let summary = Summary::new(&parse_artifacts("tests/test_assets").unwrap());
+        let json = serde_json::from_str(&JsonRenderer.render(&summary)).unwrap();
+        assert!(SchemaKind::Summary.validate(&json).is_empty());
+    }
+
+    #[test]
+    fn invalid_report() {
+        let issues =
+            validate_report(r#"{"boards": [{"board": 42, "sketches": []}], "commit_url": ""}"#)
+                .unwrap();
+        let issues = issues.iter().map(|i| i.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            issues,
+            [
+                r#"/: "commit_hash" is a required property"#,
+                r#"/boards/0/board: 42 is not of type "string""#,
+            ]
+        );
+        assert!(matches!(
+            validate_report("{not json"),
+            Err(JsonError::Serde(_))
+        ));
+    }
+
+    #[test]
+    fn reader() {
+        let report = fs::read_to_string("tests/size-deltas-reports-new/arduino-avr-uno.json")
+            .unwrap()
+            .replace('\n', "");
+        let ndjson = format!("{report}\n{{\"boards\": []}}\n");
+        let results = validate_reader(ndjson.as_bytes()).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_empty());
+        assert!(!results[1].is_empty());
+        let array = format!("[{report}, {report}]");
+        assert_eq!(validate_reader(array.as_bytes()).unwrap(), [vec![], vec![]]);
+    }
+}
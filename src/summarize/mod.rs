@@ -1,10 +1,18 @@
 //! A module to define functions that generate a markdown comment.
-mod helpers;
-use std::path::Path;
+use std::{io::Read, path::Path};
+pub mod model;
+pub mod regressions;
+pub mod render;
 mod structs;
-use crate::{CommentAssemblyError, reports::parse_artifacts};
-pub use helpers::COMMENT_MARKER;
-use helpers::{generate_detailed_table, generate_general_table};
+use crate::{
+    CommentAssemblyError,
+    reports::{DiscoveryOptions, parse_artifacts_with, parse_reader, structs::Report},
+};
+use model::{SizeFormat, Summary, SymbolSet};
+use regressions::{
+    BoardStatus, Regression, Severity, Thresholds, board_statuses, find_regressions,
+};
+use render::{MarkdownRenderer, Renderer};
 
 /// Generate the comment to be posted for a PR.
 ///
@@ -13,31 +21,159 @@ use helpers::{generate_detailed_table, generate_general_table};
 ///
 /// When successful, this returns a [`String`] in markdown syntax.
 pub fn generate_comment<P: AsRef<Path>>(sketches_path: P) -> Result<String, CommentAssemblyError> {
-    let mut reports = parse_artifacts(&sketches_path)?;
+    let reports = load_reports(sketches_path)?;
+    Ok(assemble_comment(&reports))
+}
+
+/// Parse the JSON files in the given `sketches_path` into a sorted list of [`Report`]s.
+///
+/// Unlike [`parse_artifacts()`](crate::parse_artifacts), this returns [`CommentAssemblyError::NotFound`]
+/// if no applicable data was found.
+/// The [`Report`]s are sorted by the FQBN of their first board.
+pub fn load_reports<P: AsRef<Path>>(sketches_path: P) -> Result<Vec<Report>, CommentAssemblyError> {
+    load_reports_with(sketches_path, &DiscoveryOptions::default())
+}
+
+/// Like [`load_reports()`], but only the files that satisfy the given `options` are parsed
+/// (see [`parse_artifacts_with()`]).
+pub fn load_reports_with<P: AsRef<Path>>(
+    sketches_path: P,
+    options: &DiscoveryOptions,
+) -> Result<Vec<Report>, CommentAssemblyError> {
+    let reports = parse_artifacts_with(&sketches_path, options)?;
+    let source = format!("in path {}", sketches_path.as_ref().to_string_lossy());
+    sort_reports(reports, &source)
+}
+
+/// Parse the reports read from the given `reader` (for example, stdin)
+/// into a sorted list of [`Report`]s.
+///
+/// See [`parse_reader()`] for the supported data, and [`load_reports()`]
+/// about the returned [`Report`]s.
+pub fn load_reports_from_reader<R: Read>(reader: R) -> Result<Vec<Report>, CommentAssemblyError> {
+    sort_reports(parse_reader(reader)?, "from the given input")
+}
+
+/// Sort the given `reports` by the FQBN of their first board.
+///
+/// Returns [`CommentAssemblyError::NotFound`] if there are no `reports`
+/// (read from the described `source`).
+fn sort_reports(
+    mut reports: Vec<Report>,
+    source: &str,
+) -> Result<Vec<Report>, CommentAssemblyError> {
     if reports.is_empty() {
         log::error!(
-            "No delta size data found in the PR's artifacts (in path {}). \
-            Ensure the `enable-size-deltas-report` input for `arduino/compile-sketches` action is enabled.",
-            sketches_path.as_ref().to_string_lossy()
+            "No delta size data found in the PR's artifacts ({source}). \
+            Ensure the `enable-size-deltas-report` input for `arduino/compile-sketches` action is enabled."
         );
         return Err(CommentAssemblyError::NotFound);
     }
     reports.sort_by_key(|k| k.boards[0].board.clone());
+    Ok(reports)
+}
+
+/// Assemble the markdown comment from the given (non-empty) `reports`.
+///
+/// See [`load_reports()`] to get the `reports` from a path to JSON files.
+/// This is a shortcut for rendering a [`Summary`] with the [`MarkdownRenderer`].
+pub fn assemble_comment(reports: &[Report]) -> String {
+    MarkdownRenderer::default().render(&Summary::new(reports))
+}
+
+/// The options used by [`render_reports()`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// The symbols used to emphasize increases and decreases.
+    pub symbols: SymbolSet,
+
+    /// The options used to format changes in memory size.
+    pub sizes: SizeFormat,
+
+    /// The limits used to find [`RenderedReports::regressions`].
+    pub thresholds: Thresholds,
+
+    /// The URL of the repository that the reports were compiled from (if known).
+    ///
+    /// See [`Summary::for_repository()`].
+    pub repository_url: Option<String>,
+
+    /// The SHA hash of the commit that the reports are expected to be compiled from (if known).
+    ///
+    /// See [`Summary::with_expected_commit()`].
+    pub expected_commit: Option<String>,
+}
+
+/// The markdown comment (with structured metadata) returned by [`render_reports()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedReports {
+    /// The markdown comment (as posted by this crate).
+    pub markdown: String,
 
-    let mut comment = String::from(COMMENT_MARKER);
-    comment.push_str(format!("### Memory usage change @ {}\n\n", reports[0].commit_hash).as_str());
+    /// The aggregated data that the [`RenderedReports::markdown`] was rendered from.
+    pub summary: Summary,
 
-    generate_general_table(&reports, &mut comment);
-    generate_detailed_table(&reports, &mut comment);
+    /// The sketches' growth in size that exceeded the [`RenderOptions::thresholds`].
+    pub regressions: Vec<Regression>,
 
-    Ok(comment)
+    /// The overall change in size per board.
+    pub statuses: Vec<BoardStatus>,
+}
+
+impl RenderedReports {
+    /// Get the most severe of the [`RenderedReports::regressions`] (if any).
+    pub fn severity(&self) -> Option<Severity> {
+        self.regressions.iter().map(|r| r.severity).max()
+    }
+}
+
+/// Render the given `reports` (for example, built in memory) into a markdown comment
+/// with the given `options`.
+///
+/// Unlike [`generate_comment()`], this does not read any files.
+///
+/// ```
+/// use arduino_report_size_deltas::{
+///     RenderOptions, Severity, Thresholds, render_reports,
+///     report_structs::{Board, Report, Sketch, SketchSize},
+/// };
+///
+/// let report = Report::new("651f05f", "").with_board(
+///     Board::new("arduino:avr:uno")
+///         .with_maximums(32256, 2048)
+///         .with_sketch(Sketch::new("examples/Blink").with_flash(SketchSize::new(1024, Some(900)))),
+/// );
+/// let options = RenderOptions {
+///     thresholds: Thresholds { warning: Some(0), error: Some(100) },
+///     ..Default::default()
+/// };
+/// let rendered = render_reports(&[report], &options);
+/// assert!(rendered.markdown.contains("| arduino:avr:uno |"));
+/// assert_eq!(rendered.severity(), Some(Severity::Error));
+/// ```
+pub fn render_reports(reports: &[Report], options: &RenderOptions) -> RenderedReports {
+    let summary = Summary::for_repository(reports, options.repository_url.as_deref())
+        .with_expected_commit(options.expected_commit.as_deref());
+    let renderer = MarkdownRenderer {
+        symbols: options.symbols,
+        sizes: options.sizes,
+    };
+    RenderedReports {
+        markdown: renderer.render(&summary),
+        summary,
+        regressions: find_regressions(reports, &options.thresholds),
+        statuses: board_statuses(reports, &options.thresholds),
+    }
 }
 
 #[cfg(test)]
 mod test {
     #![allow(clippy::unwrap_used)]
 
-    use super::{CommentAssemblyError, generate_comment};
+    use super::{
+        CommentAssemblyError, RenderOptions, Thresholds, assemble_comment, generate_comment,
+        load_reports, load_reports_from_reader, render_reports,
+    };
     use std::fs;
 
     #[test]
@@ -48,8 +184,23 @@ mod test {
 
     #[test]
     fn use_old_test_assets() {
+        let comment = generate_comment("tests/size-deltas-reports-old").unwrap();
+        assert!(comment.contains("arduino:samd:mkrgsm1400"));
+        // the other report has no applicable data
+        assert!(!comment.contains("arduino:mbed_portenta:envie_m7"));
+        fs::write("tests/size-deltas-reports-old/out.md", comment).unwrap();
+    }
+
+    #[test]
+    fn no_applicable_assets() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::copy(
+            "tests/size-deltas-reports-old/arduino-mbed_portenta-envie_m7.json",
+            dir.path().join("report.json"),
+        )
+        .unwrap();
         assert!(matches!(
-            generate_comment("tests/size-deltas-reports-old"),
+            generate_comment(dir.path()),
             Err(CommentAssemblyError::NotFound)
         ));
     }
@@ -59,4 +210,32 @@ mod test {
         let comment = generate_comment("tests/test_assets").unwrap();
         fs::write("tests/test_assets/out.md", comment).unwrap();
     }
+
+    #[test]
+    fn render_in_memory() {
+        let reports = load_reports("tests/test_assets").unwrap();
+        let options = RenderOptions {
+            thresholds: Thresholds {
+                warning: Some(0),
+                error: None,
+            },
+            ..Default::default()
+        };
+        let rendered = render_reports(&reports, &options);
+        assert_eq!(rendered.markdown, assemble_comment(&reports));
+        assert_eq!(rendered.statuses.len(), rendered.summary.boards.len());
+        assert!(!rendered.regressions.is_empty());
+    }
+
+    #[test]
+    fn reader_assets() {
+        let text =
+            fs::read_to_string("tests/size-deltas-reports-new/arduino-avr-uno.json").unwrap();
+        let reports = load_reports_from_reader(format!("[{text}]").as_bytes()).unwrap();
+        assert_eq!(reports.len(), 1);
+        assert!(matches!(
+            load_reports_from_reader("[]".as_bytes()),
+            Err(CommentAssemblyError::NotFound)
+        ));
+    }
 }
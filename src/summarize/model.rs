@@ -0,0 +1,424 @@
+//! A module to declare the data model that is passed to a [`Renderer`](crate::Renderer).
+//!
+//! The data model is aggregated from [`crate::report_structs`] independently of any output format.
+use super::structs::SizeSummary;
+pub use super::structs::{ByteUnits, SizeDeltaRange, SizeFormat, SizeKind, SymbolSet, Trend};
+use crate::reports::structs::{
+    Board, BoardSize, Report, SizeBreakdown, SizeValue, Sketch, SketchDeltaSize, SketchSizeKind,
+};
+use serde::Serialize;
+
+/// The summary of changes in memory size for all [`Report`]s.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Summary {
+    /// The SHA hash of the commit from which compilation was performed.
+    ///
+    /// This is taken from the first [`Report`].
+    pub commit_hash: String,
+
+    /// The URL of the commit referenced by the [`Summary::commit_hash`].
+    pub commit_url: String,
+
+    /// The URL of the source tree at the [`Summary::commit_hash`] (empty if unknown).
+    ///
+    /// This is derived from the [`Summary::commit_url`] or (as a fallback)
+    /// the repository URL given to [`Summary::for_repository()`].
+    pub source_url: String,
+
+    /// The summaries per board (in the order of the given [`Report`]s).
+    pub boards: Vec<BoardSummary>,
+
+    /// The commits from which compilation was performed (in the order of the given [`Report`]s).
+    ///
+    /// There should only be one commit. More commits mean that some reports are stale
+    /// (see [`Summary::mismatched_commits()`]).
+    pub commits: Vec<CommitSummary>,
+
+    /// The SHA hash of the commit that the reports are expected to be compiled from
+    /// (empty if unknown).
+    ///
+    /// See [`Summary::with_expected_commit()`].
+    pub expected_commit: String,
+
+    /// The [`Summary::commits`] that do not match the [`Summary::expected_commit`].
+    ///
+    /// This is empty if all reports were compiled from the expected commit
+    /// (or if no commit is expected).
+    pub stale_commits: Vec<CommitSummary>,
+}
+
+impl Summary {
+    /// Aggregate the given `reports` into a [`Summary`].
+    ///
+    /// See [`load_reports()`](crate::load_reports) to get the `reports` from a path to JSON files.
+    pub fn new(reports: &[Report]) -> Self {
+        Self::for_repository(reports, None)
+    }
+
+    /// Aggregate the given `reports` into a [`Summary`] (see [`Summary::new()`]).
+    ///
+    /// The `repository_url` (for example, `https://github.com/owner/repo`) is used to link
+    /// the sketches of reports whose [`Report::commit_url`] does not point to a commit.
+    pub fn for_repository(reports: &[Report], repository_url: Option<&str>) -> Self {
+        let fallback = repository_url.map(|url| url.trim_end_matches('/'));
+        let (commit_hash, mut commit_url) = reports
+            .first()
+            .map(|r| (r.commit_hash.clone(), r.commit_url.clone()))
+            .unwrap_or_default();
+        let repository = commit_repository(&commit_hash, &commit_url).or(fallback);
+        let source_url = tree_url(repository, &commit_hash);
+        if commit_url.is_empty()
+            && let Some(repo) = &repository
+        {
+            commit_url = format!("{repo}/commit/{commit_hash}");
+        }
+        let mut boards = Vec::<BoardSummary>::new();
+        let mut commits = Vec::<CommitSummary>::new();
+        for report in reports {
+            // each report links its sketches to the commit that it was compiled from
+            let repository =
+                commit_repository(&report.commit_hash, &report.commit_url).or(fallback);
+            let report_source_url = tree_url(repository, &report.commit_hash);
+            for board in &report.boards {
+                let mut summary = BoardSummary::from(board);
+                if !report_source_url.is_empty() {
+                    for sketch in &mut summary.sketches {
+                        let path = sketch.name.trim_start_matches("./").replace(' ', "%20");
+                        sketch.url = format!("{report_source_url}/{path}");
+                    }
+                }
+                boards.push(summary);
+            }
+            let board_names = report.boards.iter().map(|b| b.board.clone());
+            match commits
+                .iter_mut()
+                .find(|c| c.commit_hash == report.commit_hash)
+            {
+                Some(commit) => commit.boards.extend(board_names),
+                None => commits.push(CommitSummary {
+                    commit_hash: report.commit_hash.clone(),
+                    commit_url: report.commit_url.clone(),
+                    boards: board_names.collect(),
+                }),
+            }
+        }
+        Self {
+            commit_hash,
+            commit_url,
+            source_url,
+            boards,
+            commits,
+            ..Default::default()
+        }
+    }
+
+    /// Set the [`Summary::expected_commit`] and find the [`Summary::stale_commits`]
+    /// (see [`Summary::mismatched_commits()`]).
+    ///
+    /// Nothing is changed if no commit is `expected`.
+    pub fn with_expected_commit(mut self, expected: Option<&str>) -> Self {
+        if let Some(expected) = expected.filter(|sha| !sha.is_empty()) {
+            self.stale_commits = self
+                .mismatched_commits(Some(expected))
+                .into_iter()
+                .cloned()
+                .collect();
+            self.expected_commit = expected.to_string();
+        }
+        self
+    }
+
+    /// Get the [`Summary::commits`] that do not match the `expected` commit's SHA hash.
+    ///
+    /// If no commit is `expected`, then the [`Summary::commit_hash`] is expected.
+    /// An abbreviated SHA hash matches the full SHA hash that it abbreviates.
+    pub fn mismatched_commits(&self, expected: Option<&str>) -> Vec<&CommitSummary> {
+        let expected = expected.unwrap_or(&self.commit_hash);
+        self.commits
+            .iter()
+            .filter(|c| {
+                let (a, b) = (c.commit_hash.as_str(), expected);
+                a.is_empty() || b.is_empty() || !(a.starts_with(b) || b.starts_with(a))
+            })
+            .collect()
+    }
+
+    /// Get the abbreviated [`Summary::commit_hash`] (the first 7 characters).
+    pub fn short_hash(&self) -> &str {
+        self.commit_hash.get(..7).unwrap_or(&self.commit_hash)
+    }
+
+    /// Get the summaries per board sorted by the boards' FQBN.
+    ///
+    /// If a board is summarized more than once, then only the last summary is kept.
+    pub fn sorted_boards(&self) -> Vec<&BoardSummary> {
+        let mut boards = self
+            .boards
+            .iter()
+            .enumerate()
+            .filter(|(i, b)| !self.boards[i + 1..].iter().any(|o| o.board == b.board))
+            .map(|(_, b)| b)
+            .collect::<Vec<_>>();
+        boards.sort_by_key(|b| b.board.as_str());
+        boards
+    }
+}
+
+/// The boards that were compiled from a single commit.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CommitSummary {
+    /// The SHA hash of the commit (see [`Report::commit_hash`]).
+    pub commit_hash: String,
+
+    /// The URL of the commit (see [`Report::commit_url`]).
+    pub commit_url: String,
+
+    /// The FQBN of the boards compiled from the commit.
+    pub boards: Vec<String>,
+}
+
+impl CommitSummary {
+    /// Get the abbreviated [`CommitSummary::commit_hash`] (the first 7 characters).
+    pub fn short_hash(&self) -> &str {
+        self.commit_hash.get(..7).unwrap_or(&self.commit_hash)
+    }
+}
+
+/// Get the URL of the repository from the given `commit_url`.
+///
+/// Returns [`None`] if the `commit_url` does not point to the given `commit_hash`.
+fn commit_repository<'a>(commit_hash: &str, commit_url: &'a str) -> Option<&'a str> {
+    if commit_hash.is_empty() {
+        return None;
+    }
+    commit_url.strip_suffix(format!("/commit/{commit_hash}").as_str())
+}
+
+/// Get the URL of the source tree at the given `commit_hash` (empty if unknown).
+fn tree_url(repository_url: Option<&str>, commit_hash: &str) -> String {
+    match repository_url {
+        Some(repo) if !commit_hash.is_empty() => format!("{repo}/tree/{commit_hash}"),
+        _ => String::new(),
+    }
+}
+
+/// The summary of changes in memory size for a single board.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BoardSummary {
+    /// The board's FQBN (see [`Board::board`]).
+    pub board: String,
+
+    /// The range of changes in flash memory size of all the board's sketches.
+    pub flash: SizeDeltaRange,
+
+    /// The range of changes in RAM size of all the board's sketches.
+    pub ram: SizeDeltaRange,
+
+    /// The board's flash memory capacity in bytes (see [`Board::sizes`]).
+    pub flash_maximum: SizeValue<u64>,
+
+    /// The board's RAM capacity in bytes (see [`Board::sizes`]).
+    pub ram_maximum: SizeValue<u64>,
+
+    /// The summaries per sketch.
+    pub sketches: Vec<SketchSummary>,
+}
+
+impl From<&Board> for BoardSummary {
+    fn from(board: &Board) -> Self {
+        let mut size_summary = SizeSummary::default();
+        for sketch in &board.sketches {
+            for size in &sketch.sizes {
+                size_summary.add(size);
+            }
+        }
+        let (mut flash_maximum, mut ram_maximum) = Default::default();
+        for size in board.sizes.iter().flatten() {
+            let (maximum, range) = match size {
+                BoardSize::Flash { .. } => (&mut flash_maximum, &mut size_summary.flash),
+                BoardSize::Ram { .. } => (&mut ram_maximum, &mut size_summary.ram),
+            };
+            *maximum = size.maximum().unwrap_or_default();
+            // older JSON formats report the range of changes per board
+            if let Some(delta) = size.delta() {
+                *range = SizeDeltaRange::from(delta);
+            }
+        }
+        Self {
+            board: board.board.clone(),
+            flash: size_summary.flash,
+            ram: size_summary.ram,
+            flash_maximum,
+            ram_maximum,
+            sketches: board.sketches.iter().map(SketchSummary::from).collect(),
+        }
+    }
+}
+
+/// The changes in memory size of a single sketch.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SketchSummary {
+    /// The sketch's relative path (see [`Sketch::name`]).
+    pub name: String,
+
+    /// The URL of the sketch's source at the commit (empty if unknown).
+    ///
+    /// This points into the source tree of the commit that the sketch's [`Report`]
+    /// was compiled from (which is not necessarily the [`Summary::source_url`]).
+    pub url: String,
+
+    /// The change in flash memory size (if reported).
+    pub flash: Option<SketchDeltaSize>,
+
+    /// The change in RAM size (if reported).
+    pub ram: Option<SketchDeltaSize>,
+
+    /// The current flash memory usage in bytes.
+    pub flash_usage: SizeValue<i64>,
+
+    /// The current RAM usage in bytes.
+    pub ram_usage: SizeValue<i64>,
+
+    /// The changes in size of the sketch's symbols and object files (if known).
+    ///
+    /// See [`Sketch::breakdown`].
+    pub breakdown: Option<SizeBreakdown>,
+}
+
+impl From<&Sketch> for SketchSummary {
+    fn from(sketch: &Sketch) -> Self {
+        let mut summary = Self {
+            name: sketch.name.clone(),
+            breakdown: sketch.breakdown.clone(),
+            ..Default::default()
+        };
+        for size in &sketch.sizes {
+            match size {
+                SketchSizeKind::Flash { size } => {
+                    summary.flash = Some(size.get_delta().clone());
+                    summary.flash_usage = size.current.absolute;
+                }
+                SketchSizeKind::Ram { size } => {
+                    summary.ram = Some(size.get_delta().clone());
+                    summary.ram_usage = size.current.absolute;
+                }
+            }
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::Summary;
+    use crate::{
+        reports::{parse_artifacts, parse_report},
+        summary_structs::SizeFormat,
+    };
+
+    const HASH: &str = "651f05f4d4aca30ac359e972c01568f873112d43";
+    const NANO_HASH: &str = "0f002640a13649c0259c76911737f0f698907b76";
+
+    #[test]
+    fn consistent_commits() {
+        let mut reports = parse_artifacts("tests/test_assets").unwrap();
+        reports.retain(|r| r.commit_hash == HASH);
+        let summary = Summary::new(&reports);
+        assert_eq!(summary.commits.len(), 1);
+        assert_eq!(summary.commits[0].boards.len(), 3);
+        assert!(summary.mismatched_commits(None).is_empty());
+        assert!(summary.mismatched_commits(Some("651f05f")).is_empty());
+        assert_eq!(summary.mismatched_commits(Some("deadbeef")).len(), 1);
+
+        let summary = summary.with_expected_commit(Some("651f05f"));
+        assert_eq!(summary.expected_commit, "651f05f");
+        assert!(summary.stale_commits.is_empty());
+        let summary = summary.with_expected_commit(Some("deadbeef"));
+        assert_eq!(summary.stale_commits.len(), 1);
+        assert_eq!(summary.stale_commits[0].boards.len(), 3);
+    }
+
+    #[test]
+    fn inconsistent_commits() {
+        let summary = Summary::new(&parse_artifacts("tests/test_assets").unwrap());
+        assert_eq!(summary.commits.len(), 2);
+        let mismatched = summary.mismatched_commits(Some(HASH));
+        assert_eq!(mismatched.len(), 1);
+        assert_eq!(mismatched[0].short_hash(), "0f00264");
+        assert_eq!(mismatched[0].boards, ["arduino:avr:nano"]);
+    }
+
+    #[test]
+    fn sketch_urls_per_report() {
+        let summary = Summary::new(&parse_artifacts("tests/test_assets").unwrap());
+        let nano = summary
+            .boards
+            .iter()
+            .find(|b| b.board == "arduino:avr:nano")
+            .unwrap();
+        assert_ne!(summary.commit_hash, NANO_HASH);
+        assert!(nano.sketches[0].url.starts_with(&format!(
+            "https://github.com/nRF24/RF24Network/tree/{}/",
+            NANO_HASH
+        )));
+        let other = summary
+            .boards
+            .iter()
+            .find(|b| b.board != "arduino:avr:nano")
+            .unwrap();
+        assert!(!summary.source_url.is_empty());
+        assert!(other.sketches[0].url.starts_with(&summary.source_url));
+    }
+
+    #[test]
+    fn fallback_repository_url() {
+        let report = parse_report(
+            r#"{"commit_hash": "deadbeef", "commit_url": "", "boards": [{
+            "board": "arduino:avr:uno", "sizes": [],
+            "sketches": [{"name": "./examples/My Blink", "compilation_success": true, "sizes": []}]
+            }]}"#,
+        )
+        .unwrap();
+        let summary = Summary::new(std::slice::from_ref(&report));
+        assert_eq!(summary.source_url, "");
+        assert_eq!(summary.boards[0].sketches[0].url, "");
+        let summary = Summary::for_repository(&[report], Some("https://example.com/owner/repo/"));
+        assert_eq!(
+            summary.commit_url,
+            "https://example.com/owner/repo/commit/deadbeef"
+        );
+        assert_eq!(
+            summary.boards[0].sketches[0].url,
+            "https://example.com/owner/repo/tree/deadbeef/examples/My%20Blink"
+        );
+    }
+
+    #[test]
+    fn board_delta_range() {
+        // an old report whose sketches do not report any changes
+        let report = parse_report(
+            r#"{"board": "arduino:avr:uno", "commit_hash": "deadbeef", "commit_url": "",
+            "sketches": [{"name": "Foo", "compilation_success": true, "sizes": [
+                {"name": "flash", "current": {"absolute": 444}}
+            ]}],
+            "sizes": [{"name": "flash", "delta": {
+                "absolute": {"minimum": -12, "maximum": 34},
+                "relative": {"minimum": -0.04, "maximum": 0.1}
+            }}]}"#,
+        )
+        .unwrap();
+        let summary = Summary::new(&[report]);
+        let flash = &summary.boards[0].flash;
+        assert_eq!(flash.absolute_range(SizeFormat::default()), "-12 - +34");
+        assert_eq!(
+            flash.summarize_relative(SizeFormat::default()),
+            "-0.04 - +0.1"
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! A module to detect sketches whose compile size grew beyond a configured threshold.
+use super::model::{SizeFormat, Summary};
+use crate::reports::structs::{Board, Report, SizeValue, SketchSizeKind};
+use std::fmt::Display;
+
+/// The limits (in bytes) used to classify growth of a sketch's compile size.
+///
+/// A limit set to [`None`] is not checked.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Thresholds {
+    /// Growth greater than this value is considered a [`Severity::Warning`].
+    pub warning: Option<i64>,
+
+    /// Growth greater than this value is considered a [`Severity::Error`].
+    ///
+    /// This takes precedence over [`Thresholds::warning`].
+    pub error: Option<i64>,
+}
+
+impl Thresholds {
+    /// Classify the given `delta` (in bytes) according to the configured limits.
+    ///
+    /// Returns [`None`] if the `delta` does not exceed any limit.
+    pub fn classify(&self, delta: i64) -> Option<Severity> {
+        if self.error.is_some_and(|limit| delta > limit) {
+            Some(Severity::Error)
+        } else if self.warning.is_some_and(|limit| delta > limit) {
+            Some(Severity::Warning)
+        } else {
+            None
+        }
+    }
+
+    /// Are any limits configured?
+    pub fn is_empty(&self) -> bool {
+        self.warning.is_none() && self.error.is_none()
+    }
+}
+
+/// The severity of a [`Regression`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The growth exceeded [`Thresholds::warning`].
+    Warning,
+    /// The growth exceeded [`Thresholds::error`].
+    Error,
+}
+
+/// The kind of memory that a [`Regression`] describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryKind {
+    /// Flash memory (program storage).
+    Flash,
+    /// RAM used for global variables.
+    Ram,
+}
+
+impl Display for MemoryKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MemoryKind::Flash => write!(f, "flash"),
+            MemoryKind::Ram => write!(f, "RAM"),
+        }
+    }
+}
+
+/// A data structure to describe a sketch's growth in compile size
+/// that exceeded a configured [`Thresholds`] limit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Regression {
+    /// The board's FQBN (see [`Board::board`](crate::report_structs::Board::board)).
+    pub board: String,
+
+    /// The sketch's relative path (see [`Sketch::name`](crate::report_structs::Sketch::name)).
+    pub sketch: String,
+
+    /// The sketch's primary source file (see [`Sketch::main_file()`](crate::report_structs::Sketch::main_file)).
+    pub file: String,
+
+    /// The kind of memory that grew.
+    pub memory: MemoryKind,
+
+    /// The growth in bytes.
+    pub delta: i64,
+
+    /// The severity as determined by the [`Thresholds`].
+    pub severity: Severity,
+}
+
+/// Find all sketches in the given `reports` with a size growth that exceeds the given `thresholds`.
+///
+/// Each sketch can yield up to 2 [`Regression`]s; one for [`MemoryKind::Flash`]
+/// and one for [`MemoryKind::Ram`].
+/// Sizes that are not applicable (eg. no previous compilation) are ignored.
+pub fn find_regressions(reports: &[Report], thresholds: &Thresholds) -> Vec<Regression> {
+    let mut regressions = vec![];
+    for report in reports {
+        for board in &report.boards {
+            regressions.extend(board_regressions(board, thresholds));
+        }
+    }
+    regressions
+}
+
+/// Find the sketches of the given `board` with a size growth that exceeds the given `thresholds`
+/// (see [`find_regressions()`]).
+fn board_regressions(board: &Board, thresholds: &Thresholds) -> Vec<Regression> {
+    let mut regressions = vec![];
+    if thresholds.is_empty() {
+        return regressions;
+    }
+    for sketch in &board.sketches {
+        for size in &sketch.sizes {
+            let memory = match size {
+                SketchSizeKind::Flash { .. } => MemoryKind::Flash,
+                SketchSizeKind::Ram { .. } => MemoryKind::Ram,
+            };
+            let SizeValue::Known(delta) = size.get_size().get_delta().absolute else {
+                continue;
+            };
+            if let Some(severity) = thresholds.classify(delta) {
+                regressions.push(Regression {
+                    board: board.board.clone(),
+                    sketch: sketch.name.clone(),
+                    file: sketch.main_file(),
+                    memory,
+                    delta,
+                    severity,
+                });
+            }
+        }
+    }
+    regressions
+}
+
+/// A data structure to describe the overall change in a board's compile sizes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardStatus {
+    /// The board's FQBN (see [`Board::board`](crate::report_structs::Board::board)).
+    pub board: String,
+
+    /// A short description of the board's changes in flash and RAM usage.
+    ///
+    /// For example, `"flash +124 B, RAM -8 B"`.
+    /// A range of values is shown if the board's sketches changed by different amounts.
+    pub description: String,
+
+    /// The most severe [`Regression`] found for the board's sketches (if any).
+    pub severity: Option<Severity>,
+}
+
+/// Summarize the changes in compile size per board in the given `reports`.
+///
+/// The statuses are sorted by the boards' FQBN. If a board is found in more than one report,
+/// then only the last report's data is used (like [`Summary::sorted_boards()`]).
+/// The [`BoardStatus::severity`] is determined by the given `thresholds`.
+pub fn board_statuses(reports: &[Report], thresholds: &Thresholds) -> Vec<BoardStatus> {
+    let summary = Summary::new(reports);
+    summary
+        .sorted_boards()
+        .into_iter()
+        .map(|summary| {
+            let severity = reports
+                .iter()
+                .rev()
+                .flat_map(|r| &r.boards)
+                .find(|b| b.board == summary.board)
+                .and_then(|board| {
+                    board_regressions(board, thresholds)
+                        .iter()
+                        .map(|r| r.severity)
+                        .max()
+                });
+            BoardStatus {
+                board: summary.board.clone(),
+                description: format!(
+                    "{} {}, {} {}",
+                    MemoryKind::Flash,
+                    summary.flash.headline(SizeFormat::default()),
+                    MemoryKind::Ram,
+                    summary.ram.headline(SizeFormat::default())
+                ),
+                severity,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::{MemoryKind, Severity, Thresholds, board_statuses, find_regressions};
+    use crate::reports::parse_artifacts;
+
+    #[test]
+    fn classify() {
+        let thresholds = Thresholds {
+            warning: Some(0),
+            error: Some(64),
+        };
+        assert_eq!(thresholds.classify(-8), None);
+        assert_eq!(thresholds.classify(0), None);
+        assert_eq!(thresholds.classify(32), Some(Severity::Warning));
+        assert_eq!(thresholds.classify(84), Some(Severity::Error));
+    }
+
+    #[test]
+    fn no_thresholds() {
+        let reports = parse_artifacts("tests/test_assets").unwrap();
+        assert!(find_regressions(&reports, &Thresholds::default()).is_empty());
+    }
+
+    #[test]
+    fn actual_assets() {
+        let reports = parse_artifacts("tests/test_assets").unwrap();
+        let thresholds = Thresholds {
+            warning: Some(0),
+            error: Some(64),
+        };
+        let mut regressions = find_regressions(&reports, &thresholds);
+        regressions.sort_by_key(|r| r.delta);
+        assert_eq!(regressions.len(), 2);
+
+        let ram = &regressions[0];
+        assert_eq!(ram.board, "arduino:samd:mkrzero");
+        assert_eq!(ram.file, "examples/MIDIUSB_write/MIDIUSB_write.ino");
+        assert_eq!(ram.memory, MemoryKind::Ram);
+        assert_eq!(ram.delta, 32);
+        assert_eq!(ram.severity, Severity::Warning);
+
+        let flash = &regressions[1];
+        assert_eq!(flash.sketch, "examples/MIDIUSB_clock");
+        assert_eq!(flash.memory, MemoryKind::Flash);
+        assert_eq!(flash.delta, 84);
+        assert_eq!(flash.severity, Severity::Error);
+    }
+
+    #[test]
+    fn statuses() {
+        let reports = parse_artifacts("tests/test_assets").unwrap();
+        let thresholds = Thresholds {
+            warning: Some(0),
+            error: Some(64),
+        };
+        let statuses = board_statuses(&reports, &thresholds);
+        let expected = [
+            (
+                "arduino:avr:leonardo",
+                "flash -40 to 0 B, RAM -16 to 0 B",
+                None,
+            ),
+            ("arduino:avr:nano", "flash 0 B, RAM 0 B", None),
+            ("arduino:sam:arduino_due_x", "flash 0 B, RAM N/A", None),
+            (
+                "arduino:samd:mkrzero",
+                "flash -28 to +84 B, RAM 0 to +32 B",
+                Some(Severity::Error),
+            ),
+        ];
+        assert_eq!(statuses.len(), expected.len());
+        for (status, (board, description, severity)) in statuses.iter().zip(expected) {
+            assert_eq!(status.board, board);
+            assert_eq!(status.description, description);
+            assert_eq!(status.severity, severity);
+        }
+    }
+
+    #[test]
+    fn duplicate_boards() {
+        let fresh = parse_artifacts("tests/test_assets").unwrap();
+        let mut stale = fresh.clone();
+        for board in stale.iter_mut().flat_map(|r| &mut r.boards) {
+            board.sketches.clear();
+        }
+        let thresholds = Thresholds {
+            warning: Some(0),
+            error: Some(64),
+        };
+
+        // the last report of each board is used
+        let statuses = board_statuses(&[stale.clone(), fresh.clone()].concat(), &thresholds);
+        assert_eq!(statuses.len(), 4);
+        let mkrzero = &statuses[3];
+        assert_eq!(mkrzero.board, "arduino:samd:mkrzero");
+        assert_eq!(mkrzero.description, "flash -28 to +84 B, RAM 0 to +32 B");
+        assert_eq!(mkrzero.severity, Some(Severity::Error));
+
+        let statuses = board_statuses(&[fresh, stale].concat(), &thresholds);
+        assert_eq!(statuses.len(), 4);
+        assert!(statuses.iter().all(|s| s.severity.is_none()));
+    }
+}
@@ -0,0 +1,418 @@
+//! A module to render a [`Summary`] as HTML.
+use super::{DETAILED_HEADER, GENERAL_HEADER, Renderer};
+use crate::{
+    reports::structs::{SizeValue, SketchDeltaSize},
+    summarize::model::{SizeDeltaRange, SizeFormat, Summary, SymbolSet, Trend},
+};
+
+/// The header row of a board's table in the [`HtmlReportRenderer`] output.
+const REPORT_HEADER: [&str; 7] = [
+    "Sketch",
+    "Flash",
+    "%",
+    "Flash usage",
+    "RAM for global variables",
+    "%",
+    "RAM usage",
+];
+
+/// The style sheet embedded in the [`HtmlReportRenderer`] output.
+const REPORT_CSS: &str = include_str!("report.css");
+
+/// The script embedded in the [`HtmlReportRenderer`] output to sort tables.
+const REPORT_JS: &str = include_str!("report.js");
+
+/// Escape the characters in `text` that have a special meaning in HTML.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Get the CSS class name for a [`Trend`].
+fn trend_class(trend: Option<Trend>) -> &'static str {
+    match trend {
+        Some(Trend::Increase) => "increase",
+        Some(Trend::Decrease) => "decrease",
+        Some(Trend::Mixed) => "mixed",
+        Some(Trend::Unchanged) => "unchanged",
+        None => "not-applicable",
+    }
+}
+
+/// Render a table cell with a CSS class describing the `trend`.
+fn cell(text: &str, trend: Option<Trend>) -> String {
+    format!(
+        r#"<td class="{}">{}</td>"#,
+        trend_class(trend),
+        escape(text)
+    )
+}
+
+/// Render a table cell with a CSS class describing the `trend` and a value to sort by.
+fn sortable_cell<T: ToString>(text: &str, trend: Option<Trend>, sort: &SizeValue<T>) -> String {
+    match sort {
+        SizeValue::Known(v) => format!(
+            r#"<td class="{}" data-sort="{}">{}</td>"#,
+            trend_class(trend),
+            v.to_string(),
+            escape(text)
+        ),
+        SizeValue::NotApplicable => cell(text, trend),
+    }
+}
+
+/// Get the table cells (absolute and relative) for a sketch's change in memory size.
+fn delta_cells(
+    delta: Option<&SketchDeltaSize>,
+    symbols: SymbolSet,
+    sizes: SizeFormat,
+) -> [String; 2] {
+    match delta {
+        Some(delta) => {
+            let trend = Trend::of(&delta.absolute);
+            let relative = match &delta.relative {
+                Some(relative) => sortable_cell(&sizes.relative(relative), trend, relative),
+                // like the other formats, a relative change that was not reported is left empty
+                None => cell("", trend),
+            };
+            [
+                sortable_cell(
+                    &symbols.summarize_delta(Some(delta), sizes),
+                    trend,
+                    &delta.absolute,
+                ),
+                relative,
+            ]
+        }
+        None => ["<td></td>".to_string(), "<td></td>".to_string()],
+    }
+}
+
+/// Get the table cells (absolute and relative) for a board's range of changes in memory size.
+///
+/// The cells are sorted by the range's maximum.
+fn range_cells(range: &SizeDeltaRange, symbols: SymbolSet, sizes: SizeFormat) -> [String; 2] {
+    let trend = range.trend();
+    [
+        sortable_cell(
+            &range.summarize_absolute(symbols, sizes),
+            trend,
+            &range.maximum.absolute,
+        ),
+        sortable_cell(
+            &range.summarize_relative(sizes),
+            trend,
+            &range.maximum.relative,
+        ),
+    ]
+}
+
+/// Render a table cell with a bar that visualizes the `usage` of the board's `maximum` capacity.
+fn usage_cell(usage: &SizeValue<i64>, maximum: &SizeValue<u64>, sizes: SizeFormat) -> String {
+    match (usage, maximum) {
+        (SizeValue::Known(usage), SizeValue::Known(maximum)) if *maximum > 0 => {
+            let percent = *usage as f64 * 100.0 / *maximum as f64;
+            format!(
+                concat!(
+                    r#"<td data-sort="{percent:.2}"><div class="bar">"#,
+                    r#"<div class="fill" style="width: {width:.2}%"></div></div>"#,
+                    r#"<span class="usage">{usage} / {maximum}{unit} ({percent:.2}%)</span></td>"#
+                ),
+                percent = percent,
+                width = percent.clamp(0.0, 100.0),
+                usage = sizes.bytes(*usage),
+                maximum = sizes.bytes(i64::try_from(*maximum).unwrap_or(i64::MAX)),
+                unit = sizes.unit_suffix()
+            )
+        }
+        (SizeValue::Known(usage), _) => format!(
+            r#"<td data-sort="{usage}"><span class="usage">{}{}</span></td>"#,
+            sizes.bytes(*usage),
+            sizes.unit_suffix()
+        ),
+        _ => cell("N/A", None),
+    }
+}
+
+/// Render a table with the given `header` and (already rendered) `rows`.
+///
+/// If `sortable`, then the table's rows can be sorted by clicking a header cell
+/// (see the [`HtmlReportRenderer`]).
+fn render_table(header: &[&str], rows: &[String], sortable: bool, out: &mut String) {
+    out.push_str(if sortable {
+        "<table class=\"sortable\">\n<thead>\n<tr>"
+    } else {
+        "<table>\n<thead>\n<tr>"
+    });
+    for h in header {
+        out.push_str(format!("<th>{}</th>", escape(h)).as_str());
+    }
+    out.push_str("</tr>\n</thead>\n<tbody>\n");
+    for row in rows {
+        out.push_str(format!("<tr>{row}</tr>\n").as_str());
+    }
+    out.push_str("</tbody>\n</table>\n");
+}
+
+/// A [`Renderer`] that produces an HTML fragment.
+///
+/// Table cells have a CSS class describing the change in memory size
+/// (`increase`, `decrease`, `mixed`, `unchanged` or `not-applicable`),
+/// so the output can be styled by the page that includes it.
+#[derive(Debug, Clone, Copy)]
+pub struct HtmlRenderer {
+    /// The options used to format changes in memory size.
+    pub sizes: SizeFormat,
+
+    /// The symbols used to emphasize increases and decreases.
+    ///
+    /// No symbols are used by default (the cells' CSS classes describe the changes).
+    pub symbols: SymbolSet,
+}
+
+impl Default for HtmlRenderer {
+    fn default() -> Self {
+        Self {
+            sizes: SizeFormat::default(),
+            symbols: SymbolSet::None,
+        }
+    }
+}
+
+impl Renderer for HtmlRenderer {
+    fn render(&self, summary: &Summary) -> String {
+        let mut out = String::new();
+        if !summary.commit_hash.is_empty() {
+            out.push_str(
+                format!(
+                    "<h3>Memory usage change @ <code>{}</code></h3>\n",
+                    escape(&summary.commit_hash)
+                )
+                .as_str(),
+            );
+        }
+
+        let rows = summary
+            .sorted_boards()
+            .into_iter()
+            .map(|board| {
+                [
+                    vec![cell(&board.board, None)],
+                    range_cells(&board.flash, self.symbols, self.sizes).to_vec(),
+                    range_cells(&board.ram, self.symbols, self.sizes).to_vec(),
+                ]
+                .concat()
+                .concat()
+            })
+            .collect::<Vec<_>>();
+        render_table(&GENERAL_HEADER, &rows, false, &mut out);
+
+        out.push_str("<details>\n<summary>Click for full report per board</summary>\n");
+        for board in &summary.boards {
+            out.push_str(format!("<h4><code>{}</code></h4>\n", escape(&board.board)).as_str());
+            let rows = board
+                .sketches
+                .iter()
+                .map(|sketch| {
+                    [
+                        vec![cell(&sketch.name, None)],
+                        delta_cells(sketch.flash.as_ref(), self.symbols, self.sizes).to_vec(),
+                        delta_cells(sketch.ram.as_ref(), self.symbols, self.sizes).to_vec(),
+                    ]
+                    .concat()
+                    .concat()
+                })
+                .collect::<Vec<_>>();
+            render_table(&DETAILED_HEADER, &rows, false, &mut out);
+        }
+        out.push_str("</details>\n");
+        out
+    }
+}
+
+/// A [`Renderer`] that produces a self-contained HTML document.
+///
+/// The document has no external assets (the CSS and script are inline),
+/// so it can be uploaded as an artifact or attached to release notes.
+/// All tables can be sorted by clicking a column's header.
+/// Changes in memory size are color-coded, and each sketch's current usage
+/// is visualized as a bar relative to the board's capacity (see [`Board::sizes`](crate::report_structs::Board::sizes)).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HtmlReportRenderer {
+    /// The options used to format changes in memory size.
+    pub sizes: SizeFormat,
+}
+
+impl Renderer for HtmlReportRenderer {
+    fn render(&self, summary: &Summary) -> String {
+        let mut out = format!(
+            concat!(
+                "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n",
+                "<title>Memory usage change @ {}</title>\n<style>\n{}</style>\n</head>\n<body>\n"
+            ),
+            escape(&summary.commit_hash),
+            REPORT_CSS
+        );
+        let commit = format!("<code>{}</code>", escape(&summary.commit_hash));
+        let commit = if summary.commit_url.is_empty() {
+            commit
+        } else {
+            format!(r#"<a href="{}">{commit}</a>"#, escape(&summary.commit_url))
+        };
+        out.push_str(format!("<h1>Memory usage change @ {commit}</h1>\n").as_str());
+
+        out.push_str("<h2>Overview</h2>\n");
+        let rows = summary
+            .sorted_boards()
+            .into_iter()
+            .map(|board| {
+                [
+                    vec![cell(&board.board, None)],
+                    range_cells(&board.flash, SymbolSet::None, self.sizes).to_vec(),
+                    range_cells(&board.ram, SymbolSet::None, self.sizes).to_vec(),
+                ]
+                .concat()
+                .concat()
+            })
+            .collect::<Vec<_>>();
+        render_table(&GENERAL_HEADER, &rows, true, &mut out);
+
+        for board in &summary.boards {
+            out.push_str(format!("<h2><code>{}</code></h2>\n", escape(&board.board)).as_str());
+            let rows = board
+                .sketches
+                .iter()
+                .map(|sketch| {
+                    let [flash_abs, flash_rel] =
+                        delta_cells(sketch.flash.as_ref(), SymbolSet::None, self.sizes);
+                    let [ram_abs, ram_rel] =
+                        delta_cells(sketch.ram.as_ref(), SymbolSet::None, self.sizes);
+                    [
+                        cell(&sketch.name, None),
+                        flash_abs,
+                        flash_rel,
+                        usage_cell(&sketch.flash_usage, &board.flash_maximum, self.sizes),
+                        ram_abs,
+                        ram_rel,
+                        usage_cell(&sketch.ram_usage, &board.ram_maximum, self.sizes),
+                    ]
+                    .concat()
+                })
+                .collect::<Vec<_>>();
+            render_table(&REPORT_HEADER, &rows, true, &mut out);
+        }
+        out.push_str(format!("<script>\n{REPORT_JS}</script>\n</body>\n</html>\n").as_str());
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::{HtmlRenderer, HtmlReportRenderer, delta_cells, escape, usage_cell};
+    use crate::{
+        Renderer,
+        reports::{
+            parse_artifacts,
+            structs::{SizeValue, SketchDeltaSize},
+        },
+        summarize::model::{ByteUnits, SizeFormat, Summary, SymbolSet},
+    };
+
+    #[test]
+    fn escaped() {
+        assert_eq!(escape("<a & \"b\">"), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+
+    #[test]
+    fn tables() {
+        let reports = parse_artifacts("tests/test_assets").unwrap();
+        let out = HtmlRenderer::default().render(&Summary::new(&reports));
+        assert!(out.starts_with(
+            "<h3>Memory usage change @ <code>651f05f4d4aca30ac359e972c01568f873112d43</code></h3>"
+        ));
+        assert!(out.contains(concat!(
+            r#"<tr><td class="not-applicable">arduino:samd:mkrzero</td>"#,
+            r#"<td class="mixed" data-sort="84">-28 - +84</td>"#
+        )));
+        assert!(out.contains(concat!(
+            r#"<td class="increase" data-sort="84">+84</td>"#,
+            r#"<td class="increase" data-sort="0.03">+0.03</td>"#
+        )));
+        assert_eq!(out.matches("<table>").count(), 5);
+    }
+
+    #[test]
+    fn standalone_report() {
+        let reports = parse_artifacts("tests/test_assets").unwrap();
+        let out = HtmlReportRenderer::default().render(&Summary::new(&reports));
+        assert!(out.starts_with("<!DOCTYPE html>"));
+        assert!(out.ends_with("</html>\n"));
+        // no external assets
+        assert!(!out.contains("<link"));
+        assert!(!out.contains(" src="));
+        assert_eq!(out.matches(r#"<table class="sortable">"#).count(), 5);
+        assert!(out.contains(concat!(
+            r#"<a href="https://github.com/per1234/generate-size-deltas-report/commit/"#,
+            r#"651f05f4d4aca30ac359e972c01568f873112d43">"#
+        )));
+        // capacity bar of examples/MIDIUSB_clock for arduino:samd:mkrzero
+        assert!(out.contains(r#"<span class="usage">11976 / 262144 B (4.57%)</span>"#));
+
+        let renderer = HtmlReportRenderer {
+            sizes: SizeFormat {
+                units: ByteUnits::Binary,
+                ..Default::default()
+            },
+        };
+        let out = renderer.render(&Summary::new(&reports));
+        assert!(out.contains(r#"<span class="usage">11.7 KiB / 256 KiB (4.57%)</span>"#));
+    }
+
+    #[test]
+    fn usage_without_capacity() {
+        assert_eq!(
+            usage_cell(
+                &SizeValue::Known(42),
+                &SizeValue::NotApplicable,
+                SizeFormat::default()
+            ),
+            r#"<td data-sort="42"><span class="usage">42 B</span></td>"#
+        );
+        assert_eq!(
+            usage_cell(
+                &SizeValue::NotApplicable,
+                &SizeValue::Known(1024),
+                SizeFormat::default()
+            ),
+            r#"<td class="not-applicable">N/A</td>"#
+        );
+    }
+
+    #[test]
+    fn without_relative_delta() {
+        let delta = SketchDeltaSize {
+            absolute: SizeValue::Known(4),
+            relative: None,
+        };
+        let cells = delta_cells(Some(&delta), SymbolSet::None, SizeFormat::default());
+        assert_eq!(
+            cells,
+            [
+                r#"<td class="increase" data-sort="4">+4</td>"#,
+                r#"<td class="increase"></td>"#
+            ]
+        );
+    }
+}
@@ -0,0 +1,44 @@
+//! A module to render a [`Summary`] as JSON.
+use super::Renderer;
+use crate::summarize::model::Summary;
+
+/// A [`Renderer`] that serializes the [`Summary`] data model as (pretty-printed) JSON.
+///
+/// Sizes that are not applicable are serialized as `"N/A"` (like the input JSON files).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JsonRenderer;
+
+impl Renderer for JsonRenderer {
+    fn render(&self, summary: &Summary) -> String {
+        // The data model only has string keys and numeric or string values,
+        // so serialization cannot fail.
+        serde_json::to_string_pretty(summary).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::JsonRenderer;
+    use crate::{Renderer, reports::parse_artifacts, summarize::model::Summary};
+
+    #[test]
+    fn serialized() {
+        let reports = parse_artifacts("tests/test_assets").unwrap();
+        let out = JsonRenderer.render(&Summary::new(&reports));
+        let value = serde_json::from_str::<serde_json::Value>(&out).unwrap();
+        assert_eq!(
+            value["commit_hash"],
+            "651f05f4d4aca30ac359e972c01568f873112d43"
+        );
+        let due = value["boards"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|b| b["board"] == "arduino:sam:arduino_due_x")
+            .unwrap();
+        assert_eq!(due["ram"]["maximum"]["absolute"], "N/A");
+        assert_eq!(due["sketches"][0]["flash"]["absolute"], 0);
+    }
+}
@@ -0,0 +1,417 @@
+//! A module to render a [`Summary`] as a markdown comment.
+//! See [`crate::summarize::generate_comment()`] for API meant for public consumption.
+use super::{DETAILED_HEADER, GENERAL_HEADER, Renderer};
+use crate::{
+    reports::structs::{ComponentSize, LibrarySize, SizeBreakdown, SizeValue, SketchDeltaSize},
+    summarize::model::{SizeFormat, SketchSummary, Summary, SymbolSet, Trend},
+};
+
+/// A prefix to identify bot comments from markdown text.
+pub const COMMENT_MARKER: &str = "<!-- 2bndy5/arduino-report-size-deltas -->\n";
+
+/// The maximum comment length (in bytes). This limitation is imposed by GitHub REST API.
+pub(super) const MAX_COMMENT_LEN: usize = 65536;
+
+/// A reusable divider for constructing 5-column markdown tables.
+const TABLE_DIVIDER: &str = "|---|---|---|---|---|\n";
+
+/// The start of a note that lists which boards were compiled from which commit.
+const MIXED_COMMITS: &str =
+    "> [!WARNING]\n> The reports were compiled from different commits:\n>\n";
+
+/// The start of a note that lists which boards were not compiled from the expected commit.
+const STALE_COMMITS: &str =
+    "> [!WARNING]\n> The reports were not compiled from the expected commit";
+
+/// The start of a collapsed detailed report.
+const START_DETAILS: &str = "\n<details><summary>Click for full report per board</summary>\n";
+
+/// The end of a collapsed detailed report.
+const END_DETAILS: &str = "\n</details>\n";
+
+/// The number of largest increases (and decreases) listed per sketch's size breakdown.
+pub(super) const TOP_CHANGES: usize = 10;
+
+/// A function used to ensure the given `len_limit` is respected when
+/// appending the specified `new_data` text to the mutably referenced `existing_comment`.
+///
+/// The `usize` returned is the new `len_limit` after altering the `existing_comment`.
+/// If the `existing_comment` is not altered, then the given `len_limit` is returned.
+fn append_to_comment(existing_comment: &mut String, new_data: &str, len_limit: usize) -> usize {
+    let new_len = new_data.len();
+    if existing_comment.len() + new_len < len_limit {
+        existing_comment.push_str(new_data);
+        return len_limit - new_len;
+    }
+    len_limit
+}
+
+/// Get a markdown link with the given `text` to the given `url`.
+///
+/// The `text` is not linked if the `url` is empty.
+fn link(text: &str, url: &str) -> String {
+    if url.is_empty() {
+        text.to_string()
+    } else {
+        format!("[{text}]({url})")
+    }
+}
+
+/// Get the table cells (absolute and relative) for a sketch's change in memory size.
+///
+/// The cells are empty if the change was not reported.
+fn delta_cells(
+    delta: Option<&SketchDeltaSize>,
+    symbols: SymbolSet,
+    sizes: SizeFormat,
+) -> [String; 2] {
+    match delta {
+        Some(delta) => [
+            symbols.summarize_delta(Some(delta), sizes),
+            delta
+                .relative
+                .as_ref()
+                .map(|v| sizes.relative(v))
+                .unwrap_or_default(),
+        ],
+        None => [String::new(), String::new()],
+    }
+}
+
+/// Get a table of the [`TOP_CHANGES`] largest increases and decreases of the given `components`.
+///
+/// The table is empty if no component changed in size.
+fn component_table(
+    title: &str,
+    components: &[ComponentSize],
+    symbols: SymbolSet,
+    sizes: SizeFormat,
+) -> String {
+    let top = SizeBreakdown::top_changes(components, TOP_CHANGES);
+    if top.is_empty() {
+        return String::new();
+    }
+    let mut table = format!("\n| {title} | Before | After | Change |\n|---|---|---|---|\n");
+    for component in top {
+        let delta = SizeValue::Known(component.delta());
+        table.push_str(
+            format!(
+                "| `{}` | {} | {} | {} |\n",
+                component.name,
+                sizes.bytes(component.previous as i64),
+                sizes.bytes(component.current as i64),
+                symbols.emphasize(sizes.absolute(&delta), Trend::of(&delta))
+            )
+            .as_str(),
+        );
+    }
+    table
+}
+
+/// Get a table of the flash and RAM usage of each library (if any).
+fn library_table(libraries: &[LibrarySize], symbols: SymbolSet, sizes: SizeFormat) -> String {
+    if libraries.is_empty() {
+        return String::new();
+    }
+    let change = |delta: i64| {
+        let delta = SizeValue::Known(delta);
+        symbols.emphasize(sizes.absolute(&delta), Trend::of(&delta))
+    };
+    let mut table = format!(
+        "\n| Library | {} | Change | {} | Change |\n{TABLE_DIVIDER}",
+        GENERAL_HEADER[1], GENERAL_HEADER[3]
+    );
+    for library in libraries {
+        table.push_str(
+            format!(
+                "| `{}` | {} | {} | {} | {} |\n",
+                library.name,
+                sizes.bytes(library.current_flash as i64),
+                change(library.flash_delta()),
+                sizes.bytes(library.current_ram as i64),
+                change(library.ram_delta())
+            )
+            .as_str(),
+        );
+    }
+    table
+}
+
+/// Get a collapsed section that lists the libraries of a `sketch`, and the symbols and
+/// object files with the largest changes in size.
+///
+/// This is empty if the sketch's size breakdown is unknown (or nothing changed).
+fn breakdown_details(
+    sketch: &SketchSummary,
+    board: &str,
+    symbols: SymbolSet,
+    sizes: SizeFormat,
+) -> String {
+    let Some(breakdown) = &sketch.breakdown else {
+        return String::new();
+    };
+    let tables = library_table(&breakdown.libraries, symbols, sizes)
+        + component_table("Symbol", &breakdown.symbols, symbols, sizes).as_str()
+        + component_table("Object file", &breakdown.objects, symbols, sizes).as_str();
+    if tables.is_empty() {
+        return tables;
+    }
+    format!(
+        "\n<details><summary>Size changes in `{}` (`{board}`)</summary>\n{tables}\n</details>\n",
+        sketch.name
+    )
+}
+
+/// A [`Renderer`] that produces the markdown comment posted by this crate.
+///
+/// The output starts with the [`COMMENT_MARKER`] and is limited to
+/// the maximum comment length allowed by GitHub's REST API.
+/// Increases and decreases are emphasized with the configured [`SymbolSet`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MarkdownRenderer {
+    /// The symbols used to emphasize increases and decreases.
+    pub symbols: SymbolSet,
+
+    /// The options used to format changes in memory size.
+    pub sizes: SizeFormat,
+}
+
+impl MarkdownRenderer {
+    /// Create a [`MarkdownRenderer`] that uses the given set of `symbols`.
+    pub fn with_symbols(symbols: SymbolSet) -> Self {
+        Self {
+            symbols,
+            ..Default::default()
+        }
+    }
+}
+
+impl Renderer for MarkdownRenderer {
+    fn render(&self, summary: &Summary) -> String {
+        let mut comment = String::from(COMMENT_MARKER);
+        if !summary.commit_hash.is_empty() {
+            let commit = link(summary.short_hash(), &summary.commit_url);
+            comment.push_str(format!("### Memory usage change @ {commit}\n\n").as_str());
+        }
+        if !summary.stale_commits.is_empty() {
+            let expected = summary.expected_commit.get(..7);
+            let expected = expected.unwrap_or(&summary.expected_commit);
+            comment.push_str(format!("{STALE_COMMITS} `{expected}`:\n>\n").as_str());
+        } else if summary.commits.len() > 1 {
+            comment.push_str(MIXED_COMMITS);
+        }
+        if summary.commits.len() > 1 || !summary.stale_commits.is_empty() {
+            for commit in &summary.commits {
+                let boards = commit
+                    .boards
+                    .iter()
+                    .map(|b| format!("`{b}`"))
+                    .collect::<Vec<_>>();
+                comment.push_str(
+                    format!(
+                        "> - {}: {}\n",
+                        link(&format!("`{}`", commit.short_hash()), &commit.commit_url),
+                        boards.join(", ")
+                    )
+                    .as_str(),
+                );
+            }
+            comment.push('\n');
+        }
+        generate_general_table(summary, self, &mut comment);
+        generate_detailed_table(summary, self, &mut comment);
+        comment
+    }
+}
+
+/// Create board summary table.
+///
+/// This is the short overview table that summarizes the changes in memory size.
+fn generate_general_table(summary: &Summary, options: &MarkdownRenderer, comment: &mut String) {
+    let MarkdownRenderer { symbols, sizes } = *options;
+    let mut len_limit = MAX_COMMENT_LEN - comment.len();
+    len_limit = append_to_comment(
+        comment,
+        format!("| {} |\n{TABLE_DIVIDER}", GENERAL_HEADER.join(" | ")).as_str(),
+        len_limit,
+    );
+    for board in summary.sorted_boards() {
+        let row = [
+            board.board.clone(),
+            board.flash.summarize_absolute(symbols, sizes),
+            board.flash.summarize_relative(sizes),
+            board.ram.summarize_absolute(symbols, sizes),
+            board.ram.summarize_relative(sizes),
+        ];
+        let line = row.join(" | ");
+        let new_limit = append_to_comment(comment, format!("| {line} |\n").as_str(), len_limit);
+        if len_limit == new_limit {
+            break;
+        } else {
+            len_limit = new_limit;
+        }
+    }
+}
+
+/// Create sketch summaries per board
+fn generate_detailed_table(summary: &Summary, options: &MarkdownRenderer, comment: &mut String) {
+    let MarkdownRenderer { symbols, sizes } = *options;
+    let mut len_limit = MAX_COMMENT_LEN - comment.len();
+    if len_limit > (START_DETAILS.len() + END_DETAILS.len()) {
+        len_limit = append_to_comment(comment, START_DETAILS, len_limit) - END_DETAILS.len();
+        for board in &summary.boards {
+            len_limit = append_to_comment(
+                comment,
+                format!("\n### `{}`\n\n", board.board).as_str(),
+                len_limit,
+            );
+            len_limit = append_to_comment(
+                comment,
+                format!("| {} |\n{TABLE_DIVIDER}", DETAILED_HEADER.join(" | ")).as_str(),
+                len_limit,
+            );
+            for sketch in &board.sketches {
+                let mut row = vec![link(&sketch.name, &sketch.url)];
+                row.extend(delta_cells(sketch.flash.as_ref(), symbols, sizes));
+                row.extend(delta_cells(sketch.ram.as_ref(), symbols, sizes));
+                let new_limit = append_to_comment(
+                    comment,
+                    format!("| {} |\n", row.join(" | ")).as_str(),
+                    len_limit,
+                );
+                if new_limit == len_limit {
+                    break;
+                } else {
+                    len_limit = new_limit;
+                }
+            }
+            for sketch in &board.sketches {
+                let details = breakdown_details(sketch, &board.board, symbols, sizes);
+                len_limit = append_to_comment(comment, &details, len_limit);
+            }
+        }
+        comment.push_str(END_DETAILS);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use crate::{
+        reports::{parse_artifacts, parse_json},
+        summarize::model::{ByteUnits, SizeFormat, Summary, SymbolSet},
+    };
+
+    use super::{
+        END_DETAILS, GENERAL_HEADER, MAX_COMMENT_LEN, MarkdownRenderer, Renderer, START_DETAILS,
+        generate_detailed_table, generate_general_table,
+    };
+
+    fn get_summary() -> Summary {
+        Summary::new(&[parse_json("tests/size-deltas-reports-new/arduino-avr-nano.json").unwrap()])
+    }
+
+    #[test]
+    fn max_len_general() {
+        let mut comment = String::new();
+        for _ in 0..MAX_COMMENT_LEN {
+            comment.push('.');
+        }
+        generate_general_table(&get_summary(), &MarkdownRenderer::default(), &mut comment);
+    }
+
+    fn detail_comment_maxed(already_full: bool) {
+        let mut comment = String::new();
+        let test_max = if already_full {
+            MAX_COMMENT_LEN
+        } else {
+            let empty_space = format!("| {} |", GENERAL_HEADER.join(" | ")).len() * 2;
+            MAX_COMMENT_LEN - END_DETAILS.len() - START_DETAILS.len() - empty_space
+        };
+        for _ in 0..test_max {
+            comment.push('.');
+        }
+        generate_detailed_table(&get_summary(), &MarkdownRenderer::default(), &mut comment);
+    }
+
+    #[test]
+    fn max_detail_len() {
+        detail_comment_maxed(true);
+        detail_comment_maxed(false);
+    }
+
+    #[test]
+    fn symbols() {
+        let summary = Summary::new(&parse_artifacts("tests/test_assets").unwrap());
+        let comment = MarkdownRenderer::with_symbols(SymbolSet::Text).render(&summary);
+        assert!(comment.contains("| arduino:avr:leonardo | \u{25bc} -40 - 0 |"));
+        assert!(!comment.contains(":green_heart:"));
+        let comment = MarkdownRenderer::with_symbols(SymbolSet::None).render(&summary);
+        assert!(comment.contains("| arduino:avr:leonardo | -40 - 0 |"));
+        let comment = MarkdownRenderer::default().render(&summary);
+        assert!(comment.contains("| :green_heart: -28 |"));
+    }
+
+    #[test]
+    fn size_format() {
+        let summary = Summary::new(&parse_artifacts("tests/test_assets").unwrap());
+        let renderer = MarkdownRenderer {
+            sizes: SizeFormat {
+                units: ByteUnits::Binary,
+                precision: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let comment = renderer.render(&summary);
+        assert!(
+            comment.contains("| arduino:avr:leonardo | :green_heart: -40 B - 0 B | -0.1 - 0 |")
+        );
+        assert!(comment.contains("/examples/MIDIUSB_clock) | :small_red_triangle: +84 B | 0 |"));
+    }
+
+    #[test]
+    fn links() {
+        let summary = Summary::new(&parse_artifacts("tests/test_assets").unwrap());
+        let comment = MarkdownRenderer::default().render(&summary);
+        let repo = "https://github.com/per1234/generate-size-deltas-report";
+        let hash = "651f05f4d4aca30ac359e972c01568f873112d43";
+        assert!(comment.contains(
+            format!("### Memory usage change @ [651f05f]({repo}/commit/{hash})\n").as_str()
+        ));
+        assert!(
+            comment.contains(
+                format!("| [examples/MIDIUSB_clock]({repo}/tree/{hash}/examples/MIDIUSB_clock) |")
+                    .as_str()
+            )
+        );
+    }
+
+    #[test]
+    fn mixed_commits() {
+        let summary = Summary::new(&parse_artifacts("tests/test_assets").unwrap());
+        let comment = MarkdownRenderer::default().render(&summary);
+        assert!(comment.contains(concat!(
+            "> - [`0f00264`](https://github.com/nRF24/RF24Network/commit/",
+            "0f002640a13649c0259c76911737f0f698907b76): `arduino:avr:nano`\n"
+        )));
+        let summary =
+            Summary::new(&[parse_json("tests/test_assets/arduino-avr-leonardo.json").unwrap()]);
+        let comment = MarkdownRenderer::default().render(&summary);
+        assert!(!comment.contains("different commits"));
+    }
+
+    #[test]
+    fn stale_commit() {
+        let report = parse_json("tests/test_assets/arduino-avr-leonardo.json").unwrap();
+        let summary = Summary::new(&[report]).with_expected_commit(Some("deadbeef"));
+        let comment = MarkdownRenderer::default().render(&summary);
+        assert!(comment.contains(concat!(
+            "> The reports were not compiled from the expected commit `deadbee`:\n>\n",
+            "> - [`651f05f`](https://github.com/per1234/generate-size-deltas-report/commit/",
+            "651f05f4d4aca30ac359e972c01568f873112d43): `arduino:avr:leonardo`\n"
+        )));
+        assert!(!comment.contains("different commits"));
+    }
+}
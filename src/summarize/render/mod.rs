@@ -0,0 +1,48 @@
+//! A module to define the [`Renderer`] trait and its implementations.
+//!
+//! Each [`Renderer`] formats the same [`Summary`] data model,
+//! so library consumers can implement their own format without forking this crate.
+use super::model::Summary;
+mod html;
+mod json;
+mod markdown;
+#[cfg(feature = "template")]
+mod template;
+mod text;
+pub use html::{HtmlRenderer, HtmlReportRenderer};
+pub use json::JsonRenderer;
+pub use markdown::{COMMENT_MARKER, MarkdownRenderer};
+#[cfg(feature = "template")]
+pub use template::{DEFAULT_TEMPLATE, TemplateRenderer};
+pub use text::PlainTextRenderer;
+#[cfg(feature = "terminal")]
+pub use text::TerminalRenderer;
+
+/// The text used as a header row in the overview table (per board).
+const GENERAL_HEADER: [&str; 5] = ["Board", "Flash", "%", "RAM for global variables", "%"];
+
+/// The text used as a header row in a board's detailed table (per sketch).
+const DETAILED_HEADER: [&str; 5] = ["Sketch", "Flash", "%", "RAM for global variables", "%"];
+
+/// A trait to format a [`Summary`] of the changes in memory size.
+///
+/// ```
+/// use arduino_report_size_deltas::{Renderer, summary_structs::Summary};
+///
+/// /// A renderer that only lists the boards' FQBN.
+/// struct BoardList;
+///
+/// impl Renderer for BoardList {
+///     fn render(&self, summary: &Summary) -> String {
+///         summary
+///             .sorted_boards()
+///             .iter()
+///             .map(|b| format!("- {}\n", b.board))
+///             .collect()
+///     }
+/// }
+/// ```
+pub trait Renderer {
+    /// Render the given `summary` into a [`String`].
+    fn render(&self, summary: &Summary) -> String;
+}
@@ -0,0 +1,27 @@
+// Sort a table's rows when a header cell is clicked.
+// Cells with a `data-sort` attribute are sorted by that (numeric) value instead of their text.
+document.querySelectorAll("table.sortable th").forEach((th) => {
+  th.addEventListener("click", () => {
+    const table = th.closest("table");
+    const body = table.tBodies[0];
+    const index = th.cellIndex;
+    const ascending = th.dataset.order !== "asc";
+    table.querySelectorAll("th").forEach((h) => delete h.dataset.order);
+    th.dataset.order = ascending ? "asc" : "desc";
+    const key = (row) => {
+      const cell = row.cells[index];
+      const value = cell.dataset.sort ?? cell.textContent;
+      const number = parseFloat(value);
+      return isNaN(number) ? value : number;
+    };
+    const rows = Array.from(body.rows).sort((a, b) => {
+      const [x, y] = [key(a), key(b)];
+      const order =
+        typeof x === "number" && typeof y === "number"
+          ? x - y
+          : String(x).localeCompare(String(y));
+      return ascending ? order : -order;
+    });
+    body.append(...rows);
+  });
+});
@@ -0,0 +1,299 @@
+//! A module to render a [`Summary`] with a user-defined [minijinja] template.
+//!
+//! The template is given the following variables:
+//!
+//! - `summary`: The [`Summary`] (including all boards in the order they were reported).
+//! - `boards`: The boards sorted by FQBN (see [`Summary::sorted_boards()`]).
+//!
+//! The following filters are also available:
+//!
+//! - `summarize`: A [`SizeDeltaRange`] as a symbol followed by the absolute range
+//!   (see [`TemplateRenderer::with_symbols()`]).
+//! - `summarize_range`: A [`SizeDeltaRange`] as the absolute range (without a symbol).
+//! - `summarize_relative`: A [`SizeDeltaRange`] as the relative range (in percent).
+//! - `headline`: A [`SizeDeltaRange`] as a short sentence (see [`SizeDeltaRange::headline()`]).
+//! - `trend`: The [`Trend`] of a [`SizeDeltaRange`] (or nothing if not applicable).
+//! - `summarize_delta`: A sketch's change in memory size as a symbol followed by the absolute value.
+//! - `absolute`/`relative`: A sketch's change in memory size (or nothing if not reported).
+//! - `top_changes`: The largest increases and decreases of a list of [`ComponentSize`]s
+//!   (see [`SizeBreakdown::top_changes()`]).
+//! - `summarize_change`: A [`ComponentSize`]'s change as a symbol followed by the absolute value.
+//! - `summarize_bytes`: A change in size (in bytes) as a symbol followed by the absolute value.
+//! - `bytes`: A number of bytes (without a "+" prefix).
+//!
+//! All values are formatted with the [`SizeFormat`] given to [`TemplateRenderer::with_sizes()`].
+use super::{
+    Renderer,
+    markdown::{COMMENT_MARKER, MAX_COMMENT_LEN, MarkdownRenderer, TOP_CHANGES},
+};
+use crate::{
+    error::TemplateError,
+    reports::structs::{ComponentSize, SizeBreakdown, SizeValue, SketchDeltaSize},
+    summarize::model::{SizeDeltaRange, SizeFormat, Summary, SymbolSet, Trend},
+};
+use minijinja::{Environment, context, value::ViaDeserialize};
+use std::{fs, path::Path};
+
+/// The name used to register the template.
+const TEMPLATE_NAME: &str = "comment";
+
+/// The built-in template that reproduces the output of the [`MarkdownRenderer`].
+pub const DEFAULT_TEMPLATE: &str = include_str!("comment.md.jinja");
+
+/// A [`Renderer`] that produces a comment from a [minijinja] template.
+///
+/// The output always starts with the [`COMMENT_MARKER`] and is truncated (at a line boundary)
+/// to the maximum comment length allowed by GitHub's REST API.
+#[derive(Debug)]
+pub struct TemplateRenderer {
+    env: Environment<'static>,
+    symbols: SymbolSet,
+    sizes: SizeFormat,
+}
+
+impl TemplateRenderer {
+    /// Create a [`TemplateRenderer`] from the given `template` source.
+    ///
+    /// Returns an error if the `template` has invalid syntax.
+    pub fn new(template: String) -> Result<Self, TemplateError> {
+        let mut env = Environment::new();
+        env.set_trim_blocks(true);
+        env.set_lstrip_blocks(true);
+        env.set_keep_trailing_newline(true);
+        env.add_filter("trend", |range: ViaDeserialize<SizeDeltaRange>| {
+            range.trend().map(|trend| match trend {
+                Trend::Decrease => "decrease",
+                Trend::Unchanged => "unchanged",
+                Trend::Increase => "increase",
+                Trend::Mixed => "mixed",
+            })
+        });
+        env.add_filter(
+            "top_changes",
+            |components: ViaDeserialize<Vec<ComponentSize>>| {
+                SizeBreakdown::top_changes(&components, TOP_CHANGES)
+                    .into_iter()
+                    .cloned()
+                    .map(minijinja::Value::from_serialize)
+                    .collect::<Vec<_>>()
+            },
+        );
+        env.add_template_owned(TEMPLATE_NAME, template)?;
+        let mut renderer = Self {
+            env,
+            symbols: SymbolSet::default(),
+            sizes: SizeFormat::default(),
+        };
+        renderer.add_format_filters();
+        Ok(renderer)
+    }
+
+    /// Use the given set of `symbols` to emphasize increases and decreases
+    /// (in the `summarize` and `summarize_delta` filters).
+    pub fn with_symbols(mut self, symbols: SymbolSet) -> Self {
+        self.symbols = symbols;
+        self.add_format_filters();
+        self
+    }
+
+    /// Use the given `sizes` options to format changes in memory size (in all filters).
+    pub fn with_sizes(mut self, sizes: SizeFormat) -> Self {
+        self.sizes = sizes;
+        self.add_format_filters();
+        self
+    }
+
+    /// Create a [`TemplateRenderer`] from the template in the file at the given `path`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, TemplateError> {
+        Self::new(fs::read_to_string(path)?)
+    }
+
+    /// Create a [`TemplateRenderer`] from the [`DEFAULT_TEMPLATE`].
+    pub fn builtin() -> Result<Self, TemplateError> {
+        Self::new(DEFAULT_TEMPLATE.to_string())
+    }
+
+    /// Add (or replace) the filters that depend on the configured symbols and size format.
+    fn add_format_filters(&mut self) {
+        let (symbols, sizes) = (self.symbols, self.sizes);
+        self.env
+            .add_filter("summarize", move |range: ViaDeserialize<SizeDeltaRange>| {
+                range.summarize_absolute(symbols, sizes)
+            });
+        self.env.add_filter(
+            "summarize_range",
+            move |range: ViaDeserialize<SizeDeltaRange>| range.absolute_range(sizes),
+        );
+        self.env.add_filter(
+            "summarize_relative",
+            move |range: ViaDeserialize<SizeDeltaRange>| range.summarize_relative(sizes),
+        );
+        self.env
+            .add_filter("headline", move |range: ViaDeserialize<SizeDeltaRange>| {
+                range.headline(sizes)
+            });
+        self.env.add_filter(
+            "summarize_delta",
+            move |delta: ViaDeserialize<Option<SketchDeltaSize>>| {
+                symbols.summarize_delta(delta.as_ref(), sizes)
+            },
+        );
+        self.env.add_filter(
+            "summarize_change",
+            move |component: ViaDeserialize<ComponentSize>| {
+                let delta = SizeValue::Known(component.delta());
+                symbols.emphasize(sizes.absolute(&delta), Trend::of(&delta))
+            },
+        );
+        self.env.add_filter("summarize_bytes", move |delta: i64| {
+            let delta = SizeValue::Known(delta);
+            symbols.emphasize(sizes.absolute(&delta), Trend::of(&delta))
+        });
+        self.env
+            .add_filter("bytes", move |value: i64| sizes.bytes(value));
+        self.env.add_filter(
+            "absolute",
+            move |delta: ViaDeserialize<Option<SketchDeltaSize>>| {
+                delta
+                    .as_ref()
+                    .map(|d| sizes.absolute(&d.absolute))
+                    .unwrap_or_default()
+            },
+        );
+        self.env.add_filter(
+            "relative",
+            move |delta: ViaDeserialize<Option<SketchDeltaSize>>| {
+                delta
+                    .as_ref()
+                    .and_then(|d| d.relative.as_ref())
+                    .map(|v| sizes.relative(v))
+                    .unwrap_or_default()
+            },
+        );
+    }
+
+    /// Render the given `summary`, returning any error raised by the template.
+    pub fn try_render(&self, summary: &Summary) -> Result<String, TemplateError> {
+        let template = self.env.get_template(TEMPLATE_NAME)?;
+        let body = template.render(context! {
+            summary => summary,
+            boards => summary.sorted_boards(),
+        })?;
+        let mut comment = String::from(COMMENT_MARKER);
+        comment.push_str(body.strip_prefix(COMMENT_MARKER).unwrap_or(&body));
+        if comment.len() >= MAX_COMMENT_LEN {
+            log::warn!("Truncating rendered template to the maximum comment length");
+            let end = comment[..MAX_COMMENT_LEN]
+                .rfind('\n')
+                .map(|i| i + 1)
+                .unwrap_or_default();
+            comment.truncate(end);
+        }
+        Ok(comment)
+    }
+}
+
+impl Renderer for TemplateRenderer {
+    /// Render the given `summary`.
+    ///
+    /// If the template fails to render, then the error is logged and
+    /// the [`MarkdownRenderer`] is used instead (with the same symbols and size format).
+    fn render(&self, summary: &Summary) -> String {
+        self.try_render(summary).unwrap_or_else(|e| {
+            log::error!("Failed to render comment template: {e}");
+            MarkdownRenderer {
+                symbols: self.symbols,
+                sizes: self.sizes,
+            }
+            .render(summary)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::{MAX_COMMENT_LEN, TemplateRenderer};
+    use crate::{
+        COMMENT_MARKER, MarkdownRenderer, Renderer,
+        error::TemplateError,
+        reports::{parse_artifacts, parse_elf},
+        summarize::model::{Summary, SymbolSet},
+    };
+
+    #[test]
+    fn builtin_matches_markdown() {
+        for asset in ["tests/test_assets", "tests/size-deltas-reports-new"] {
+            let summary = Summary::new(&parse_artifacts(asset).unwrap());
+            let stale = summary.clone().with_expected_commit(Some("deadbeef"));
+            let comment = TemplateRenderer::builtin().unwrap().try_render(&stale);
+            assert_eq!(comment.unwrap(), MarkdownRenderer::default().render(&stale));
+            let comment = TemplateRenderer::builtin().unwrap().try_render(&summary);
+            assert_eq!(
+                comment.unwrap(),
+                MarkdownRenderer::default().render(&summary)
+            );
+            let comment = TemplateRenderer::builtin()
+                .unwrap()
+                .with_symbols(SymbolSet::Unicode)
+                .try_render(&summary);
+            let expected = MarkdownRenderer::with_symbols(SymbolSet::Unicode).render(&summary);
+            assert_eq!(comment.unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn builtin_breakdown() {
+        let report = parse_elf("tests/elf/base", "tests/elf/head").unwrap();
+        let summary = Summary::new(&[report]);
+        let comment = TemplateRenderer::builtin().unwrap().try_render(&summary);
+        let expected = MarkdownRenderer::default().render(&summary);
+        assert!(expected.contains("| `Blink.ino.cpp.o` | 60 | 92 | :small_red_triangle: +32 |"));
+        assert!(expected.contains("| `sketch` | 92 | :small_red_triangle: +32 | 0 | 0 |"));
+        assert_eq!(comment.unwrap(), expected);
+    }
+
+    #[test]
+    fn custom() {
+        let summary = Summary::new(&parse_artifacts("tests/test_assets").unwrap());
+        let template = "{% for board in boards %}\n\
+            {{ board.board }}: {{ board.flash | trend }} ({{ board.flash | summarize_range }})\n\
+            {% endfor %}";
+        let comment = TemplateRenderer::new(template.to_string())
+            .unwrap()
+            .render(&summary);
+        assert!(comment.starts_with(COMMENT_MARKER));
+        assert!(comment.contains("arduino:samd:mkrzero: mixed (-28 - +84)\n"));
+        assert!(comment.contains("arduino:avr:leonardo: decrease (-40 - 0)\n"));
+    }
+
+    #[test]
+    fn invalid_syntax() {
+        let result = TemplateRenderer::new("{% for board in boards %}".to_string());
+        assert!(matches!(result, Err(TemplateError::Render(_))));
+    }
+
+    #[test]
+    fn fallback() {
+        let summary = Summary::new(&parse_artifacts("tests/test_assets").unwrap());
+        let renderer = TemplateRenderer::new("{{ boards | unknown }}".to_string()).unwrap();
+        assert!(renderer.try_render(&summary).is_err());
+        assert_eq!(
+            renderer.render(&summary),
+            MarkdownRenderer::default().render(&summary)
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let summary = Summary::new(&parse_artifacts("tests/test_assets").unwrap());
+        let renderer = TemplateRenderer::new(
+            "{% for i in range(10000) %}{{ summary.commit_hash }}\n{% endfor %}".to_string(),
+        )
+        .unwrap();
+        let comment = renderer.try_render(&summary).unwrap();
+        assert!(comment.len() < MAX_COMMENT_LEN);
+        assert!(comment.ends_with('\n'));
+    }
+}
@@ -0,0 +1,372 @@
+//! A module to render a [`Summary`] as aligned text tables.
+//!
+//! The [`PlainTextRenderer`] and the [`TerminalRenderer`] (which requires the `terminal` feature)
+//! share the same layout; the latter shows increases in red and decreases in green.
+use super::{DETAILED_HEADER, GENERAL_HEADER, Renderer};
+use crate::{
+    reports::structs::SketchDeltaSize,
+    summarize::model::{SizeFormat, Summary, SymbolSet, Trend},
+};
+#[cfg(feature = "terminal")]
+use colored::Color;
+
+/// The spacing between columns.
+const COLUMN_GAP: &str = "  ";
+
+/// The styles applied to the text (after alignment).
+trait Style {
+    /// Style a heading's text.
+    fn heading(&self, text: &str) -> String;
+
+    /// Style a table's header row.
+    fn header(&self, text: &str) -> String;
+
+    /// Style a table cell's text according to a [`Trend`].
+    fn cell(&self, text: &str, trend: Option<Trend>) -> String;
+}
+
+/// A table cell's text with the [`Trend`] used to style it.
+struct Cell {
+    text: String,
+    trend: Option<Trend>,
+}
+
+impl Cell {
+    fn plain(text: String) -> Self {
+        Self { text, trend: None }
+    }
+}
+
+/// Get the table cells (absolute and relative) for a sketch's change in memory size.
+fn delta_cells(
+    delta: Option<&SketchDeltaSize>,
+    symbols: SymbolSet,
+    sizes: SizeFormat,
+) -> [Cell; 2] {
+    match delta {
+        Some(delta) => {
+            let trend = Trend::of(&delta.absolute);
+            [
+                Cell {
+                    text: symbols.summarize_delta(Some(delta), sizes),
+                    trend,
+                },
+                Cell {
+                    text: delta
+                        .relative
+                        .as_ref()
+                        .map(|v| sizes.relative(v))
+                        .unwrap_or_default(),
+                    trend,
+                },
+            ]
+        }
+        None => [Cell::plain(String::new()), Cell::plain(String::new())],
+    }
+}
+
+/// Render a table with aligned columns.
+///
+/// Padding is done before styling, so any escape codes do not affect the alignment.
+fn render_table(header: [&str; 5], rows: &[[Cell; 5]], style: &impl Style, out: &mut String) {
+    let mut widths = header.map(|h| h.chars().count());
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.text.chars().count());
+        }
+    }
+    let last = header.len() - 1;
+    let header_line = header
+        .iter()
+        .enumerate()
+        .map(|(i, h)| {
+            let width = if i == last { 0 } else { widths[i] };
+            format!("{h:<width$}")
+        })
+        .collect::<Vec<_>>()
+        .join(COLUMN_GAP);
+    out.push_str(format!("{}\n", style.header(&header_line)).as_str());
+    let total_width = widths.iter().sum::<usize>() + COLUMN_GAP.len() * last;
+    out.push_str(format!("{}\n", "-".repeat(total_width)).as_str());
+    for row in rows {
+        let line = row
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| {
+                let width = if i == last { 0 } else { widths[i] };
+                style.cell(format!("{:<width$}", cell.text).as_str(), cell.trend)
+            })
+            .collect::<Vec<_>>()
+            .join(COLUMN_GAP);
+        out.push_str(format!("{}\n", line.trim_end()).as_str());
+    }
+}
+
+/// Render the overview table (per board) and a detailed table for each board (per sketch).
+fn render_text(
+    summary: &Summary,
+    symbols: SymbolSet,
+    sizes: SizeFormat,
+    style: &impl Style,
+) -> String {
+    let mut out = String::new();
+    if !summary.commit_hash.is_empty() {
+        let title = format!("Memory usage change @ {}", summary.commit_hash);
+        out.push_str(format!("{}\n\n", style.heading(&title)).as_str());
+    }
+
+    let rows = summary
+        .sorted_boards()
+        .into_iter()
+        .map(|board| {
+            let (flash, ram) = (board.flash.trend(), board.ram.trend());
+            [
+                Cell::plain(board.board.clone()),
+                Cell {
+                    text: board.flash.summarize_absolute(symbols, sizes),
+                    trend: flash,
+                },
+                Cell {
+                    text: board.flash.summarize_relative(sizes),
+                    trend: flash,
+                },
+                Cell {
+                    text: board.ram.summarize_absolute(symbols, sizes),
+                    trend: ram,
+                },
+                Cell {
+                    text: board.ram.summarize_relative(sizes),
+                    trend: ram,
+                },
+            ]
+        })
+        .collect::<Vec<_>>();
+    render_table(GENERAL_HEADER, &rows, style, &mut out);
+
+    for board in &summary.boards {
+        out.push_str(format!("\n{}\n\n", style.heading(&board.board)).as_str());
+        let rows = board
+            .sketches
+            .iter()
+            .map(|sketch| {
+                let [flash_abs, flash_rel] = delta_cells(sketch.flash.as_ref(), symbols, sizes);
+                let [ram_abs, ram_rel] = delta_cells(sketch.ram.as_ref(), symbols, sizes);
+                [
+                    Cell::plain(sketch.name.clone()),
+                    flash_abs,
+                    flash_rel,
+                    ram_abs,
+                    ram_rel,
+                ]
+            })
+            .collect::<Vec<_>>();
+        render_table(DETAILED_HEADER, &rows, style, &mut out);
+    }
+    out
+}
+
+/// A [`Renderer`] that produces aligned text tables without any styling.
+#[derive(Debug, Clone, Copy)]
+pub struct PlainTextRenderer {
+    /// The options used to format changes in memory size.
+    pub sizes: SizeFormat,
+
+    /// The symbols used to emphasize increases and decreases.
+    ///
+    /// No symbols are used by default.
+    pub symbols: SymbolSet,
+}
+
+impl Default for PlainTextRenderer {
+    fn default() -> Self {
+        Self {
+            sizes: SizeFormat::default(),
+            symbols: SymbolSet::None,
+        }
+    }
+}
+
+impl Style for PlainTextRenderer {
+    fn heading(&self, text: &str) -> String {
+        text.to_string()
+    }
+
+    fn header(&self, text: &str) -> String {
+        text.to_string()
+    }
+
+    fn cell(&self, text: &str, _trend: Option<Trend>) -> String {
+        text.to_string()
+    }
+}
+
+impl Renderer for PlainTextRenderer {
+    fn render(&self, summary: &Summary) -> String {
+        render_text(summary, self.symbols, self.sizes, self)
+    }
+}
+
+/// A [`Renderer`] that produces aligned, colored text tables for a terminal.
+///
+/// Increases are shown in red and decreases in green.
+#[cfg(feature = "terminal")]
+#[derive(Debug, Clone, Copy)]
+pub struct TerminalRenderer {
+    /// The options used to format changes in memory size.
+    pub sizes: SizeFormat,
+
+    /// Style the text with ANSI escape codes?
+    ///
+    /// If disabled, then the output is the same as the [`PlainTextRenderer`]'s output.
+    /// This does not depend on whether the output is written to a terminal
+    /// (or the `NO_COLOR` environment variable); that is left to the caller.
+    pub color: bool,
+
+    /// The symbols used to emphasize increases and decreases.
+    ///
+    /// No symbols are used by default.
+    pub symbols: SymbolSet,
+}
+
+#[cfg(feature = "terminal")]
+impl Default for TerminalRenderer {
+    fn default() -> Self {
+        Self {
+            sizes: SizeFormat::default(),
+            color: true,
+            symbols: SymbolSet::None,
+        }
+    }
+}
+
+#[cfg(feature = "terminal")]
+impl TerminalRenderer {
+    /// Style the given `text` with the given ANSI SGR `codes` (if [`Self::color`] is enabled).
+    fn paint(&self, text: &str, codes: &str) -> String {
+        if self.color {
+            format!("\x1b[{codes}m{text}\x1b[0m")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+#[cfg(feature = "terminal")]
+impl Style for TerminalRenderer {
+    fn heading(&self, text: &str) -> String {
+        // bold and underlined
+        self.paint(text, "1;4")
+    }
+
+    fn header(&self, text: &str) -> String {
+        // bold
+        self.paint(text, "1")
+    }
+
+    fn cell(&self, text: &str, trend: Option<Trend>) -> String {
+        let color = match trend {
+            Some(Trend::Increase) => Color::Red,
+            Some(Trend::Decrease) => Color::Green,
+            Some(Trend::Mixed) => Color::Yellow,
+            Some(Trend::Unchanged) | None => return text.to_string(),
+        };
+        self.paint(text, &color.to_fg_str())
+    }
+}
+
+#[cfg(feature = "terminal")]
+impl Renderer for TerminalRenderer {
+    fn render(&self, summary: &Summary) -> String {
+        render_text(summary, self.symbols, self.sizes, self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::PlainTextRenderer;
+    use crate::{
+        Renderer,
+        reports::parse_artifacts,
+        summarize::model::{Summary, SymbolSet},
+    };
+
+    #[test]
+    fn aligned_columns() {
+        let reports = parse_artifacts("tests/test_assets").unwrap();
+        let out = PlainTextRenderer::default().render(&Summary::new(&reports));
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            "Memory usage change @ 651f05f4d4aca30ac359e972c01568f873112d43"
+        );
+        let header = lines[2];
+        let flash_column = header.find("Flash").unwrap();
+        let ram_column = header.find("RAM").unwrap();
+        let overview = lines
+            .iter()
+            .find(|l| l.starts_with("arduino:samd:mkrzero "))
+            .unwrap();
+        assert_eq!(overview.find("-28 - +84"), Some(flash_column));
+        assert_eq!(overview.find("0 - +32"), Some(ram_column));
+        assert!(out.contains("\narduino:avr:nano\n"));
+        assert!(!out.contains(":green_heart:"));
+    }
+
+    #[test]
+    fn text_symbols() {
+        let summary = Summary::new(&parse_artifacts("tests/test_assets").unwrap());
+        let renderer = PlainTextRenderer {
+            symbols: SymbolSet::Text,
+            ..Default::default()
+        };
+        let out = renderer.render(&summary);
+        let overview = out
+            .lines()
+            .find(|l| l.starts_with("arduino:samd:mkrzero "))
+            .unwrap();
+        assert!(overview.contains("\u{b1} -28 - +84"));
+        assert!(overview.contains("\u{25b2} 0 - +32"));
+        let clock = out
+            .lines()
+            .rfind(|l| l.starts_with("examples/MIDIUSB_clock "))
+            .unwrap();
+        assert!(clock.contains("\u{25b2} +84"));
+    }
+
+    #[cfg(feature = "terminal")]
+    #[test]
+    fn colored_cells() {
+        use super::{Style, TerminalRenderer};
+        use crate::summarize::model::Trend;
+
+        let renderer = TerminalRenderer::default();
+        let increase = renderer.cell("+84", Some(Trend::Increase));
+        assert_eq!(increase, "\x1b[31m+84\x1b[0m");
+        let decrease = renderer.cell("-28", Some(Trend::Decrease));
+        assert_eq!(decrease, "\x1b[32m-28\x1b[0m");
+        assert_eq!(renderer.cell("0", Some(Trend::Unchanged)), "0");
+    }
+
+    #[cfg(feature = "terminal")]
+    #[test]
+    fn without_color() {
+        use super::TerminalRenderer;
+
+        let summary = Summary::new(&parse_artifacts("tests/test_assets").unwrap());
+        let renderer = TerminalRenderer {
+            color: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            renderer.render(&summary),
+            PlainTextRenderer::default().render(&summary)
+        );
+        assert!(
+            TerminalRenderer::default()
+                .render(&summary)
+                .contains("\x1b[")
+        );
+    }
+}
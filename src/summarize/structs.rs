@@ -1,9 +1,11 @@
 //! A module to declare the data structures used to aggregate data from [`crate::reports::structs`].
-use crate::reports::structs::{SizeValue, SketchSizeKind};
+use crate::reports::structs::{BoardDeltaSize, SizeValue, SketchDeltaSize, SketchSizeKind};
+use serde::{Deserialize, Serialize};
 use std::{any::TypeId, fmt::Display};
 
 /// A data structure to represent absolute or relative changes in memory size.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SizeKind {
     /// The absolute value of memory size.
     ///
@@ -48,21 +50,238 @@ impl SizeKind {
     }
 }
 
+/// The units used to format a number of bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ByteUnits {
+    /// A plain number of bytes (e.g. `1048576`).
+    #[default]
+    Bytes,
+    /// The largest applicable binary unit (e.g. `1 MiB`).
+    Binary,
+}
+
+/// The options used to format changes in memory size.
+///
+/// The [`Default`] options format values the same as [`SizeKind::fmt()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeFormat {
+    /// The units used to format absolute values.
+    pub units: ByteUnits,
+
+    /// Group the digits of absolute values by thousands (e.g. `1,048,576`)?
+    pub thousands_separator: bool,
+
+    /// The maximum number of decimal places in relative values (percentages).
+    ///
+    /// Insignificant trailing zeros are always trimmed.
+    pub precision: usize,
+}
+
+impl Default for SizeFormat {
+    fn default() -> Self {
+        Self {
+            units: ByteUnits::default(),
+            thousands_separator: false,
+            precision: 3,
+        }
+    }
+}
+
+impl SizeFormat {
+    /// The binary units (with their size in bytes) in descending order.
+    const BINARY_UNITS: [(&str, f64); 3] = [
+        ("GiB", (1u64 << 30) as f64),
+        ("MiB", (1u64 << 20) as f64),
+        ("KiB", (1u64 << 10) as f64),
+    ];
+
+    /// Trim insignificant zeros (and decimal point) from a formatted decimal `number`.
+    fn trim_decimal(number: String) -> String {
+        if !number.contains('.') {
+            return number;
+        }
+        // If all decimal places were `0`, we should
+        // trim the insignificant decimal point also.
+        let trimmed = number.trim_end_matches('0').trim_end_matches('.');
+        // A negative value that was rounded to zero is not negative.
+        if trimmed == "-0" { "0" } else { trimmed }.to_string()
+    }
+
+    /// Insert a separator between each group of thousands in the given (unsigned) `number`.
+    fn group_thousands(&self, number: &str) -> String {
+        if !self.thousands_separator {
+            return number.to_string();
+        }
+        let (integer, fraction) = number.split_at(number.find('.').unwrap_or(number.len()));
+        let mut grouped = String::with_capacity(number.len() + integer.len() / 3);
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                grouped.push(',');
+            }
+            grouped.push(digit);
+        }
+        grouped.push_str(fraction);
+        grouped
+    }
+
+    /// Format a number of bytes (without a "+" prefix for positive values).
+    ///
+    /// Binary units include the unit's symbol (e.g. `1.5 KiB` or `42 B`),
+    /// whereas a plain number of bytes has no symbol (see [`Self::unit_suffix()`]).
+    pub fn bytes(&self, value: i64) -> String {
+        let sign = if value < 0 { "-" } else { "" };
+        let magnitude = value.unsigned_abs();
+        match self.units {
+            ByteUnits::Bytes => format!("{sign}{}", self.group_thousands(&magnitude.to_string())),
+            ByteUnits::Binary => {
+                for (unit, size) in Self::BINARY_UNITS {
+                    if magnitude as f64 >= size {
+                        let scaled = Self::trim_decimal(format!("{:.2}", magnitude as f64 / size));
+                        return format!("{sign}{} {unit}", self.group_thousands(&scaled));
+                    }
+                }
+                format!("{sign}{magnitude} B")
+            }
+        }
+    }
+
+    /// The suffix appended to values from [`Self::bytes()`] to denote their unit.
+    ///
+    /// This is empty for binary units because they already include a unit's symbol.
+    pub fn unit_suffix(&self) -> &'static str {
+        match self.units {
+            ByteUnits::Bytes => " B",
+            ByteUnits::Binary => "",
+        }
+    }
+
+    /// Get a pretty [`String`] representation of an absolute `value` (in bytes).
+    ///
+    /// Like [`SizeKind::fmt()`], this adds a "+" to positive numbers.
+    pub fn absolute(&self, value: &SizeValue<i64>) -> String {
+        match value {
+            SizeValue::Known(v) => {
+                let prefix = if *v > 0 { "+" } else { "" };
+                format!("{prefix}{}", self.bytes(*v))
+            }
+            SizeValue::NotApplicable => "N/A".to_string(),
+        }
+    }
+
+    /// Get a pretty [`String`] representation of a relative `value` (in percent).
+    ///
+    /// Like [`SizeKind::fmt()`], this adds a "+" to positive numbers.
+    pub fn relative(&self, value: &SizeValue<f32>) -> String {
+        match value {
+            SizeValue::Known(v) => {
+                let precision = self.precision;
+                let value = Self::trim_decimal(format!("{v:.precision$}"));
+                // A positive value that was rounded to zero is not an increase.
+                let prefix = if *v > 0.0 && value != "0" { "+" } else { "" };
+                format!("{prefix}{value}")
+            }
+            SizeValue::NotApplicable => "N/A".to_string(),
+        }
+    }
+}
+
+/// The direction of a change in memory size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trend {
+    /// The memory size decreased.
+    Decrease,
+    /// The memory size did not change.
+    Unchanged,
+    /// The memory size increased.
+    Increase,
+    /// The memory size increased for some sketches and decreased for others.
+    Mixed,
+}
+
+impl Trend {
+    /// Get the [`Trend`] of a single change in memory size.
+    ///
+    /// Returns [`None`] if the `delta` is not applicable.
+    pub fn of(delta: &SizeValue<i64>) -> Option<Self> {
+        match delta {
+            SizeValue::Known(v) => Some(match v.signum() {
+                -1 => Trend::Decrease,
+                0 => Trend::Unchanged,
+                _ => Trend::Increase,
+            }),
+            SizeValue::NotApplicable => None,
+        }
+    }
+}
+
+/// The set of symbols used to emphasize a [`Trend`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolSet {
+    /// GitHub's emoji short-codes (e.g. `:green_heart:`).
+    ///
+    /// These are only rendered as emoji by GitHub's markdown.
+    #[default]
+    GitHub,
+    /// Unicode emoji (e.g. 💚).
+    Unicode,
+    /// Simple text symbols (▼, ▲, and ±) that are legible in plain-text logs.
+    Text,
+    /// No symbols.
+    None,
+}
+
+impl SymbolSet {
+    /// Get the symbol used to emphasize the given `trend`.
+    ///
+    /// An unchanged memory size is never emphasized.
+    pub fn symbol(&self, trend: Trend) -> &'static str {
+        match (self, trend) {
+            (_, Trend::Unchanged) | (SymbolSet::None, _) => "",
+            (SymbolSet::GitHub, Trend::Decrease) => ":green_heart:",
+            (SymbolSet::GitHub, Trend::Increase) => ":small_red_triangle:",
+            (SymbolSet::GitHub, Trend::Mixed) => ":grey_question:",
+            (SymbolSet::Unicode, Trend::Decrease) => "\u{1f49a}",
+            (SymbolSet::Unicode, Trend::Increase) => "\u{1f53a}",
+            (SymbolSet::Unicode, Trend::Mixed) => "\u{2754}",
+            (SymbolSet::Text, Trend::Decrease) => "\u{25bc}",
+            (SymbolSet::Text, Trend::Increase) => "\u{25b2}",
+            (SymbolSet::Text, Trend::Mixed) => "\u{b1}",
+        }
+    }
+
+    /// Prefix the given `text` with the symbol for the given `trend` (if any).
+    pub fn emphasize(&self, text: String, trend: Option<Trend>) -> String {
+        match trend.map(|t| self.symbol(t)) {
+            Some(symbol) if !symbol.is_empty() => format!("{symbol} {text}"),
+            _ => text,
+        }
+    }
+
+    /// Get a [`String`] of a sketch's change in memory size (formatted with the given `format`)
+    /// emphasized with the symbol for its [`Trend`].
+    ///
+    /// The [`String`] is empty if the change was not reported.
+    pub fn summarize_delta(&self, delta: Option<&SketchDeltaSize>, format: SizeFormat) -> String {
+        delta
+            .map(|d| self.emphasize(format.absolute(&d.absolute), Trend::of(&d.absolute)))
+            .unwrap_or_default()
+    }
+}
+
 /// A data structure to track the minimum and maximum ranges of any changes in memory size.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SizeDeltaRange {
     /// The minimum value
     pub minimum: SizeKind,
+    /// The maximum value
     pub maximum: SizeKind,
 }
 
 impl SizeDeltaRange {
-    /// A short-code for the emoji to emphasize a decrease in memory size.
-    const EMOJI_DECREASE: &str = ":green_heart:";
-    /// A short-code for the emoji to emphasize an increase and decrease in memory size.
-    const EMOJI_AMBIGUOUS: &str = ":grey_question:";
-    /// A short-code for the emoji to emphasize an increase in memory size.
-    const EMOJI_INCREASE: &str = ":small_red_triangle:";
     /// A placeholder to represent data that is not applicable.
     const NOT_APPLICABLE: &str = "N/A";
 
@@ -117,42 +336,91 @@ impl SizeDeltaRange {
     }
 
     /// Converts an instance of [`SizeDeltaRange`] into a [`String`] of
-    /// [`SizeKind::relative`] values.
-    pub(super) fn summarize_relative(&self) -> String {
-        let min_rel = SizeKind::fmt(&self.minimum.relative);
-        let max_rel = SizeKind::fmt(&self.maximum.relative);
+    /// [`SizeKind::relative`] values (formatted with the given `format`).
+    pub fn summarize_relative(&self, format: SizeFormat) -> String {
+        let min_rel = format.relative(&self.minimum.relative);
+        let max_rel = format.relative(&self.maximum.relative);
         if [min_rel.as_str(), max_rel.as_str()].contains(&Self::NOT_APPLICABLE) {
             return Self::NOT_APPLICABLE.to_string();
         }
         format!("{min_rel} - {max_rel}")
     }
 
-    /// Converts an instance of [`SizeDeltaRange`] into a [`String`] of
-    /// [`SizeKind::absolute`] values.
-    pub(super) fn summarize_absolute(&self) -> String {
-        let emoji = {
-            if let (SizeValue::Known(min), SizeValue::Known(max)) =
-                (&self.minimum.absolute, &self.maximum.absolute)
-            {
-                if *min < 0 && *max <= 0 {
-                    Self::EMOJI_DECREASE
-                } else if *min == 0 && *max == 0 {
-                    ""
-                } else if *min >= 0 && *max > 0 {
-                    Self::EMOJI_INCREASE
-                } else {
-                    Self::EMOJI_AMBIGUOUS
-                }
+    /// Get the overall [`Trend`] of the [`SizeKind::absolute`] values.
+    ///
+    /// Returns [`None`] if the values are not applicable.
+    pub fn trend(&self) -> Option<Trend> {
+        if let (SizeValue::Known(min), SizeValue::Known(max)) =
+            (&self.minimum.absolute, &self.maximum.absolute)
+        {
+            Some(if *min < 0 && *max <= 0 {
+                Trend::Decrease
+            } else if *min == 0 && *max == 0 {
+                Trend::Unchanged
+            } else if *min >= 0 && *max > 0 {
+                Trend::Increase
             } else {
-                ""
-            }
-        };
-        let min_abs = SizeKind::fmt(&self.minimum.absolute);
-        let max_abs = SizeKind::fmt(&self.maximum.absolute);
+                Trend::Mixed
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Converts an instance of [`SizeDeltaRange`] into a [`String`] of
+    /// [`SizeKind::absolute`] values (formatted with the given `format`, without any emphasis).
+    pub fn absolute_range(&self, format: SizeFormat) -> String {
+        let min_abs = format.absolute(&self.minimum.absolute);
+        let max_abs = format.absolute(&self.maximum.absolute);
         if [min_abs.as_str(), max_abs.as_str()].contains(&Self::NOT_APPLICABLE) {
             return Self::NOT_APPLICABLE.to_string();
         }
-        format!("{emoji} {min_abs} - {max_abs}")
+        format!("{min_abs} - {max_abs}")
+    }
+
+    /// Converts an instance of [`SizeDeltaRange`] into a [`String`] of
+    /// [`SizeKind::absolute`] values (formatted with the given `format`)
+    /// emphasized with the given set of `symbols`.
+    pub fn summarize_absolute(&self, symbols: SymbolSet, format: SizeFormat) -> String {
+        let range = self.absolute_range(format);
+        if range == Self::NOT_APPLICABLE {
+            return range;
+        }
+        symbols.emphasize(range, self.trend())
+    }
+
+    /// Converts an instance of [`SizeDeltaRange`] into a short [`String`] of
+    /// [`SizeKind::absolute`] values (formatted with the given `format`).
+    ///
+    /// The range is collapsed into a single value if the minimum and maximum are equal.
+    pub fn headline(&self, format: SizeFormat) -> String {
+        let min_abs = format.absolute(&self.minimum.absolute);
+        let max_abs = format.absolute(&self.maximum.absolute);
+        let unit = format.unit_suffix();
+        if [min_abs.as_str(), max_abs.as_str()].contains(&Self::NOT_APPLICABLE) {
+            Self::NOT_APPLICABLE.to_string()
+        } else if min_abs == max_abs {
+            format!("{max_abs}{unit}")
+        } else {
+            format!("{min_abs} to {max_abs}{unit}")
+        }
+    }
+}
+
+impl From<&BoardDeltaSize> for SizeDeltaRange {
+    /// Convert the range of changes reported for a board (in older JSON formats).
+    fn from(delta: &BoardDeltaSize) -> Self {
+        let relative = delta.relative.unwrap_or_default();
+        Self {
+            minimum: SizeKind {
+                absolute: delta.absolute.minimum,
+                relative: relative.minimum,
+            },
+            maximum: SizeKind {
+                absolute: delta.absolute.maximum,
+                relative: relative.maximum,
+            },
+        }
     }
 }
 
@@ -194,10 +462,89 @@ impl SizeSummary {
 mod test {
     use crate::reports::structs::SizeValue;
 
-    use super::SizeKind;
+    use super::{ByteUnits, SizeFormat, SizeKind, SymbolSet, Trend};
 
     #[test]
     fn positive_has_plus() {
         assert_eq!(SizeKind::fmt(&SizeValue::Known(1)), "+1".to_string());
     }
+
+    #[test]
+    fn default_format() {
+        let format = SizeFormat::default();
+        for value in [-1048576, -1, 0, 1, 1048576] {
+            let value = SizeValue::Known(value);
+            assert_eq!(format.absolute(&value), SizeKind::fmt(&value));
+        }
+        for value in [-0.625, 0.0, 0.1, 1.0, 12.3456] {
+            let value = SizeValue::Known(value);
+            assert_eq!(format.relative(&value), SizeKind::fmt(&value));
+        }
+    }
+
+    #[test]
+    fn thousands_separator() {
+        let format = SizeFormat {
+            thousands_separator: true,
+            ..Default::default()
+        };
+        assert_eq!(format.absolute(&SizeValue::Known(1048576)), "+1,048,576");
+        assert_eq!(format.absolute(&SizeValue::Known(-123456)), "-123,456");
+        assert_eq!(format.absolute(&SizeValue::Known(999)), "+999");
+    }
+
+    #[test]
+    fn binary_units() {
+        let format = SizeFormat {
+            units: ByteUnits::Binary,
+            ..Default::default()
+        };
+        assert_eq!(format.absolute(&SizeValue::Known(1048576)), "+1 MiB");
+        assert_eq!(format.absolute(&SizeValue::Known(-1536)), "-1.5 KiB");
+        assert_eq!(format.absolute(&SizeValue::Known(40)), "+40 B");
+        assert_eq!(format.absolute(&SizeValue::Known(0)), "0 B");
+    }
+
+    #[test]
+    fn relative_precision() {
+        let format = SizeFormat {
+            precision: 1,
+            ..Default::default()
+        };
+        assert_eq!(format.relative(&SizeValue::Known(0.625)), "+0.6");
+        assert_eq!(format.relative(&SizeValue::Known(-0.01)), "0");
+        assert_eq!(format.relative(&SizeValue::Known(0.01)), "0");
+        assert_eq!(format.relative(&SizeValue::Known(3.0)), "+3");
+        let format = SizeFormat {
+            precision: 0,
+            ..Default::default()
+        };
+        assert_eq!(format.relative(&SizeValue::Known(10.0)), "+10");
+    }
+
+    #[test]
+    fn symbol_sets() {
+        let text = || "+1".to_string();
+        assert_eq!(
+            SymbolSet::GitHub.emphasize(text(), Some(Trend::Increase)),
+            ":small_red_triangle: +1"
+        );
+        assert_eq!(
+            SymbolSet::Unicode.emphasize(text(), Some(Trend::Decrease)),
+            "\u{1f49a} +1"
+        );
+        assert_eq!(
+            SymbolSet::Text.emphasize(text(), Some(Trend::Mixed)),
+            "\u{b1} +1"
+        );
+        assert_eq!(
+            SymbolSet::None.emphasize(text(), Some(Trend::Increase)),
+            "+1"
+        );
+        assert_eq!(
+            SymbolSet::Text.emphasize(text(), Some(Trend::Unchanged)),
+            "+1"
+        );
+        assert_eq!(SymbolSet::GitHub.emphasize(text(), None), "+1");
+    }
 }